@@ -1,20 +1,15 @@
-use crate::util::{SEPARATOR_NEWLINE, SEPARATOR_SLASH, create_new_pak, secondary_text_combined};
+use crate::bilingual_set::{BilingualPair, read_bilingual_set};
+use crate::combine_rules::RuleSet;
+use crate::combiner::{CombineSettings, PairReport, PairTables, generate_pair_pak};
+use crate::overrides::Overrides;
+use crate::util::{installed_languages, list_pak_xml_files, read_language_pak};
 
 use super::bilingual_generator_errors::BilingualGeneratorError;
 use faststr::FastStr;
 use indexmap::IndexMap;
 use path_finder::PathFinder;
-use quick_xml::Reader;
-use quick_xml::events::Event;
 use rayon::prelude::*;
-use std::{
-    collections::HashMap,
-    fs::File,
-    io::{BufRead, BufReader, Read},
-    path::PathBuf,
-    sync::Mutex,
-};
-use zip::ZipArchive;
+use std::{collections::HashMap, path::PathBuf, sync::Mutex};
 
 // Main generator struct that coordinates all operations
 #[derive(Debug)]
@@ -24,6 +19,10 @@ pub struct BilingualGenerator {
     pub files_to_process: Vec<String>,
     pub language_to_process: Vec<String>,
     pub all_data: HashMap<XmlFile, HashMap<Language, IndexMap<EntryId, LastTextValue>>>,
+    pub rule_set: RuleSet,
+    pub overrides: Overrides,
+    /// Write a `decisions.csv` for every generated pair.
+    pub decision_log: bool,
 }
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct Language(pub FastStr);
@@ -43,14 +42,8 @@ impl BilingualGenerator {
     //Constructor to initialize the generator with the working directory
     pub fn init() -> Result<Self, BilingualGeneratorError> {
         let working_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::new());
-        let files_to_process = vec![
-            String::from("text_ui_dialog.xml"),    // Dialogues
-            String::from("text_ui_quest.xml"),     // Quests
-            String::from("text_ui_tutorials.xml"), // Tutorials
-            String::from("text_ui_soul.xml"),      // Stats/Effects
-            String::from("text_ui_items.xml"),     // Items
-            String::from("text_ui_menus.xml"),     // Menus
-        ];
+        // Empty: every XML file of the primary paks is discovered when the set is acquired
+        let files_to_process = Vec::new();
         let defaut_language_to_process = vec![
             String::from("Chineses"),
             String::from("English"),
@@ -68,294 +61,111 @@ impl BilingualGenerator {
             Ok(path) => path.to_path_buf(),
             Err(_) => PathBuf::new(),
         };
+        let rule_set = RuleSet::load(&working_dir)?;
+        let overrides = Overrides::load(&working_dir)?;
         Ok(Self {
             game_path: kcd_path,
             working_dir,
             files_to_process,
             language_to_process: defaut_language_to_process,
             all_data: HashMap::new(),
+            rule_set,
+            overrides,
+            decision_log: false,
         })
     }
-    pub fn acquire_bilingual_set(&mut self) -> Result<Vec<(FastStr, FastStr)>, BilingualGeneratorError> {
-        let working_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::new());
-        // println!("Working directory: {:?}", working_dir);
-        let bilingual_set_dir = working_dir.join(r"assets\bilingual_set.txt");
-        // println!("Bilingual set directory: {:?}", bilingual_set_dir);
-        let bilingual_set_file = File::open(&bilingual_set_dir)
-            .map_err(|_| BilingualGeneratorError::InvalidBilingualSet(format!("No bilingual_set.txt in {:?}", bilingual_set_dir)))?;
-        let reader = BufReader::new(bilingual_set_file);
-        let mut bilingual_set: Vec<(FastStr, FastStr)> = vec![];
-        // start reading
-        for (_, line_result) in reader.lines().enumerate() {
-            let line: String = line_result.map_err(|_| BilingualGeneratorError::InvalidBilingualSet("Fail to get String.".to_string()))?;
-            let trimmed_line = line.trim();
-
-            // Skip empty lines
-            if trimmed_line.is_empty() {
-                continue;
-            }
-
-            // Split into primary and secondary languages
-            let parts: Vec<&str> = trimmed_line.split('+').map(|s| s.trim()).collect();
-            if parts.len() != 2 {
-                return Err(BilingualGeneratorError::InvalidBilingualSet(line));
-            }
-
-            let primary_language = parts[0].to_string();
-            let secondary_language = parts[1].to_string();
-            // Check and add primary language if missing
-            if !self.language_to_process.contains(&primary_language) {
-                self.language_to_process.push(primary_language.clone());
+    /// Loads the bilingual set from `assets` in the working directory (`bilingual_set.toml`,
+    /// or `bilingual_set.txt`) and adds the languages its pairs need. Unless `files_to_process`
+    /// was set beforehand, it becomes every XML file found in the pairs' primary paks.
+    /// Every language is checked against the paks installed in the game before anything is read.
+    pub fn acquire_bilingual_set(&mut self) -> Result<Vec<BilingualPair>, BilingualGeneratorError> {
+        let mut set_file = read_bilingual_set(&self.working_dir)?;
+        set_file.check_languages(&installed_languages(&self.game_path)?);
+        let bilingual_set = set_file.into_pairs()?;
+        for pair in &bilingual_set {
+            // Check and add every language of the pair if missing
+            for language in pair.languages() {
+                if !self.language_to_process.iter().any(|l| *l == language) {
+                    self.language_to_process.push(language.to_string());
+                }
             }
+        }
+        if self.files_to_process.is_empty() {
+            self.files_to_process = self.discover_files(bilingual_set.iter().map(|pair| pair.primary.as_str()))?;
+        }
+        Ok(bilingual_set)
+    }
 
-            // Check and add secondary language if missing
-            if !self.language_to_process.contains(&secondary_language) {
-                self.language_to_process.push(secondary_language.clone());
+    /// Every XML file in the paks of the given languages, in pak order and without duplicates.
+    /// Pairs pick their files from this list with their `include`/`exclude` globs.
+    pub fn discover_files<'a>(&self, languages: impl IntoIterator<Item = &'a str>) -> Result<Vec<String>, BilingualGeneratorError> {
+        let mut files: Vec<String> = Vec::new();
+        for language in languages {
+            for file_name in list_pak_xml_files(&self.game_path, language)? {
+                if !files.contains(&file_name) {
+                    files.push(file_name);
+                }
             }
-            bilingual_set.push((primary_language.into(), secondary_language.into()));
         }
-        Ok(bilingual_set)
+        Ok(files)
     }
 
     /// Reads XML files from the pak files located in the Localization folder and stores the Entry id
     /// and secondary text (last cell) for each XML file into self.all_data.
+    /// Without an acquired set, the files come from the paks of every language to process.
     pub fn read_xml_from_paks(&mut self) -> Result<(), BilingualGeneratorError> {
+        if self.files_to_process.is_empty() {
+            self.files_to_process = self.discover_files(self.language_to_process.iter().map(String::as_str))?;
+        }
         // Collect all_data in a thread-safe manner
         let all_data = Mutex::new(&mut self.all_data);
 
         self.language_to_process.par_iter().try_for_each(|language| {
-            let pak_filename = format!("{}_xml.pak", language);
-            let pak_path = self.game_path.join("Localization").join(&pak_filename);
-            let pak_file = File::open(&pak_path).map_err(|e| BilingualGeneratorError::PakOperationFailed {
-                operation: "opening PAK file".to_string(),
-                context: pak_path.display().to_string(),
-                source: e,
-            })?;
-
-            let mut archive = ZipArchive::new(pak_file).map_err(|e| BilingualGeneratorError::PakOperationFailed {
-                operation: "creating ZipArchive from PAK".to_string(),
-                context: pak_path.display().to_string(),
-                source: e.into(),
-            })?;
-
-            self.files_to_process.iter().try_for_each(|xml_filename| {
-                let mut xml_file = archive.by_name(xml_filename).map_err(|e| BilingualGeneratorError::PakOperationFailed {
-                    operation: "finding XML file in PAK".to_string(),
-                    context: format!("{} in {}", xml_filename, pak_filename),
-                    source: e.into(),
-                })?;
-
-                let mut content = String::new();
-                xml_file.read_to_string(&mut content).map_err(|e| BilingualGeneratorError::PakOperationFailed {
-                    operation: "reading XML content from PAK".to_string(),
-                    context: format!("{} in {}", xml_filename, pak_filename),
-                    source: e,
-                })?;
-                let mut reader = Reader::from_str(&content);
-                // reader.trim_text(true); // Reduces unnecessary allocations
-                let mut buf = Vec::with_capacity(512); // Pre-allocate buffer
-                let mut single_data = IndexMap::new();
-                let mut current_cells: Vec<String> = Vec::with_capacity(4); // Pre-allocate
-                let mut inside_row = false;
-
-                loop {
-                    match reader.read_event_into(&mut buf) {
-                        Ok(Event::Start(e)) if e.name().as_ref() == b"Row" => {
-                            inside_row = true;
-                            current_cells.clear();
-                        }
-                        Ok(Event::End(e)) if e.name().as_ref() == b"Row" => {
-                            inside_row = false;
-                            if !current_cells.is_empty() && current_cells[0] != "Entry id" {
-                                if current_cells.len() >= 3 {
-                                    single_data.insert(EntryId(current_cells[0].clone().into()), LastTextValue(current_cells[2].clone().into()));
-                                }
-                            }
-                        }
-                        Ok(Event::Text(e)) => {
-                            if inside_row {
-                                // Read the raw text (including escaped entities) as a string
-                                let raw_text = std::str::from_utf8(e.as_ref())
-                                    .map_err(|_| BilingualGeneratorError::XmlProcessingFailed("Invalid UTF-8 in XML text".to_string()))?;
-                                current_cells.push(raw_text.to_string());
-                            }
-                        }
-                        Ok(Event::Eof) => break,
-                        Err(e) => return Err(BilingualGeneratorError::XmlProcessingFailed(format!("XML error: {}", e))),
-                        _ => {}
-                    }
-                    buf.clear();
-                }
+            let language_data = read_language_pak(&self.game_path, language, &self.files_to_process)?;
 
-                // Thread-safe insertion into all_data
-                let mut guard = all_data.lock().unwrap();
-                guard
-                    .entry(XmlFile(xml_filename.clone().into()))
-                    .or_insert_with(HashMap::new)
-                    .insert(Language(language.clone().into()), single_data);
-                Ok(())
-            })
+            // Thread-safe insertion into all_data
+            let mut guard = all_data.lock().unwrap();
+            for (xml_file, single_data) in language_data {
+                guard.entry(xml_file).or_default().insert(Language(language.clone().into()), single_data);
+            }
+            Ok(())
         })
     }
 
-    pub fn process_single_bilingual(&self, primary_language: &str, secondary_language: &str) -> Result<PathBuf, BilingualGeneratorError> {
-        // Create output directory
-        let xml_output_dir = self
-            .working_dir
-            .join(format!("bilingual_xml\\{} + {}\\Localization", primary_language, secondary_language));
-        let xml_output_set: Mutex<Vec<PathBuf>> = Mutex::new(vec![]); // Use Mutex for thread safety
-
-        std::fs::create_dir_all(&xml_output_dir).map_err(|e| BilingualGeneratorError::XmlProcessingFailed(format!("Error processing XML: {}", e)))?;
-
-        // Prepare language identifiers
-        let primary_lang = Language(primary_language.to_string().into());
-        let secondary_lang = Language(secondary_language.to_string().into());
-
-        // Define separators
-        // let SEPARATOR_SLASH = "/";
-        // let SEPARATOR_NEWLINE = "\\n";
-        let menutext_too_long = ["ui_state_health_desc", "ui_state_hunger_desc", "ui_DerivStat_MaxStamina_desc"];
-        // Process each XML file in parallel
-        self.files_to_process.par_iter().for_each(|file_name| {
-            let xml_file = XmlFile(file_name.clone().into());
-            let file_data = self.all_data.get(&xml_file).ok_or(BilingualGeneratorError::XmlProcessingFailed(format!(
-                "Could not find the required XML file: {}",
-                file_name
-            )));
-
-            if let Ok(file_data) = file_data {
-                // Get entries for both languages
-                let primary_entries = file_data.get(&primary_lang).ok_or(BilingualGeneratorError::XmlProcessingFailed(format!(
-                    "Could not Get primary_entries for primary_language [{:?}].",
-                    &primary_lang
-                )));
-                let empty_map: IndexMap<EntryId, LastTextValue> = IndexMap::new();
-                let secondary_entries = file_data.get(&secondary_lang).unwrap_or(&empty_map);
-                let empty_map_cloned = empty_map.clone();
-                let english_entries = file_data.get(&Language("English".into())).unwrap_or(&empty_map_cloned);
-
-                // Build XML content
-                let mut rows = Vec::new();
-                match primary_entries {
-                    Ok(entries) => {
-                        for (entry_id, primary_text) in entries {
-                            let secondary_text = secondary_entries.get(entry_id).map(|lv| lv.0.as_str()).unwrap_or("MISSING");
-                            let english_text = english_entries.get(entry_id).map(|lv| lv.0.as_str()).unwrap_or("MISSING");
-
-                            let combined_text = match file_name.as_str() {
-                                "text_ui_menus.xml" => {
-                                    if !entry_id.0.contains("ui_helpoverlay") {
-                                        match true {
-                                            _ if entry_id.0.contains("ui_loading") || entry_id.0.contains("codex_cont") => {
-                                                secondary_text_combined(primary_text, secondary_text, SEPARATOR_NEWLINE)
-                                            }
-                                            _ if primary_text.0.chars().count() <= 6 || menutext_too_long.contains(&&*entry_id.0) => {
-                                                primary_text.0.clone().into()
-                                            }
-                                            _ if primary_text.0.chars().count() >= 20 => {
-                                                secondary_text_combined(primary_text, secondary_text, SEPARATOR_NEWLINE)
-                                            }
-
-                                            _ => secondary_text_combined(primary_text, secondary_text, SEPARATOR_SLASH),
-                                        }
-                                    } else {
-                                        primary_text.0.clone().into()
-                                    }
-                                }
-                                "text_ui_dialog.xml" => {
-                                    if secondary_text != "MISSING" {
-                                        format!("{}{}{}", primary_text.0, SEPARATOR_NEWLINE, secondary_text).into()
-                                    } else {
-                                        format!("{}{}{}", primary_text.0, SEPARATOR_NEWLINE, english_text).into()
-                                    }
-                                }
-                                "text_ui_items.xml" => {
-                                    match true {
-                                        _ if (entry_id.0.contains("step") && !entry_id.0.contains("_step_1") && primary_text.0.chars().count() >= 10)
-                                            || (entry_id.0.contains("step_1")
-                                                && (entry_id.0.contains("scatter") || entry_id.0.contains("longWeak") || entry_id.0.contains("bane"))) =>
-                                        {
-                                            primary_text.0.clone()
-                                        }
-                                        _ if primary_text.0.chars().count() >= 7 => {
-                                            secondary_text_combined(primary_text, secondary_text, SEPARATOR_NEWLINE)
-                                        }
-                                        // else
-                                        _ => secondary_text_combined(primary_text, secondary_text, SEPARATOR_SLASH),
-                                    }
-                                }
-                                "text_ui_soul.xml" => match true {
-                                    _ if primary_text.0.chars().count() <= 7 && primary_text.0 != "MISSING"
-                                        || primary_text.0.chars().count() <= 12 && entry_id.0.contains("stat_") =>
-                                    {
-                                        secondary_text_combined(primary_text, secondary_text, SEPARATOR_SLASH)
-                                    }
-                                    _ if (entry_id.0.contains("buff") && entry_id.0.contains("desc") && !entry_id.0.contains("drunkenness_desc"))
-                                        || (entry_id.0.contains("perk") && entry_id.0.contains("_desc")) =>
-                                    {
-                                        secondary_text_combined(primary_text, secondary_text, SEPARATOR_NEWLINE)
-                                    }
-                                    _ => primary_text.0.clone().into(),
-                                },
-                                _ => {
-                                    // Default case for other files
-                                    if secondary_text != "MISSING" {
-                                        format!("{}{}{}", primary_text.0, SEPARATOR_SLASH, secondary_text).into()
-                                    } else {
-                                        format!("{}{}{}", primary_text.0, SEPARATOR_SLASH, english_text).into()
-                                    }
-                                }
-                            };
+    /// The generator's rules, overrides and logging switch.
+    pub fn combine_settings(&self) -> CombineSettings<'_> {
+        CombineSettings {
+            rule_set: &self.rule_set,
+            overrides: &self.overrides,
+            decision_log: self.decision_log,
+        }
+    }
 
-                            rows.push(format!(
-                                "<Row><Cell>{}</Cell><Cell>{}</Cell><Cell>{}</Cell></Row>",
-                                entry_id.0, primary_text.0, combined_text
-                            ));
-                        }
-                    }
-                    Err(e) => {
-                        // Log the error or store it for further handling
-                        eprintln!("Error: {:?}", e);
-                        // Continue with an empty map for primary_entries
-                        for (entry_id, _) in empty_map {
-                            let english_text = english_entries.get(&entry_id).map(|lv| lv.0.as_str()).unwrap_or("MISSING");
+    /// Generates one pair and returns its output directory.
+    pub fn process_single_bilingual(&self, pair: &BilingualPair) -> Result<PathBuf, BilingualGeneratorError> {
+        self.process_single_bilingual_report(pair).map(|report| report.output_dir)
+    }
 
-                            rows.push(format!(
-                                "<Row><Cell>{}</Cell><Cell>{}</Cell><Cell>{}</Cell></Row>",
-                                entry_id.0, english_text, english_text
-                            ));
-                        }
-                    }
+    /// Generates one pair and reports what was done, for the generation summary.
+    pub fn process_single_bilingual_report(&self, pair: &BilingualPair) -> Result<PairReport, BilingualGeneratorError> {
+        let primary_lang = Language(pair.primary.clone());
+        let secondary_langs: Vec<Language> = pair.secondaries.iter().cloned().map(Language).collect();
+        let fallback_langs: Vec<Language> = pair.fallback_languages().into_iter().map(|l| Language(l.to_string().into())).collect();
+
+        generate_pair_pak(
+            &self.game_path,
+            &self.working_dir,
+            &self.files_to_process,
+            pair,
+            self.combine_settings(),
+            |xml_file| {
+                let file_data = self.all_data.get(xml_file);
+                PairTables {
+                    primary: file_data.and_then(|d| d.get(&primary_lang)),
+                    secondaries: secondary_langs.iter().map(|lang| file_data.and_then(|d| d.get(lang))).collect(),
+                    fallbacks: fallback_langs.iter().filter_map(|lang| file_data.and_then(|d| d.get(lang))).collect(),
                 }
-                // Write to file
-                let xml_content = format!("<Table>\n{}\n</Table>", rows.join("\n"));
-                let xml_output_path = xml_output_dir.join(file_name);
-
-                // Push the path to the output set
-                let mut xml_output_set = xml_output_set.lock().unwrap();
-                xml_output_set.push(xml_output_path.clone());
-
-                std::fs::write(&xml_output_path, xml_content)
-                    .map_err(|e| BilingualGeneratorError::XmlProcessingFailed(format!("Error processing XML: {}", e)))
-                    .unwrap();
-            }
-        });
-
-        // Get locked access to the path list
-        let generated_xml_paths: Vec<PathBuf> = xml_output_set.into_inner().unwrap();
-        // After parallel processing, merge results and proceed
-        match create_new_pak(generated_xml_paths.clone(), &xml_output_dir, primary_language) {
-            Ok(_) => {
-                // Parallel file deletion
-                generated_xml_paths.par_iter().for_each(|path| {
-                    if let Err(e) = std::fs::remove_file(path) {
-                        eprintln!("[Cleanup] Failed to delete {}: {}", path.display(), e);
-                    }
-                });
-
-                Ok(xml_output_dir)
-            }
-            Err(_) => Err(BilingualGeneratorError::PakCreationFailed),
-        }
+            },
+        )
     }
 }
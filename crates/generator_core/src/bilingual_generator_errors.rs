@@ -1,11 +1,32 @@
-use std::io;
+use std::{fmt, io};
 use thiserror::Error;
 
+/// One problem found in the bilingual set, at a 1-based line of the set file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetIssue {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SetIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+fn format_issues(issues: &[SetIssue]) -> String {
+    issues.iter().map(|issue| format!("\n  {}", issue)).collect()
+}
+
 #[derive(Error, Debug)]
 pub enum BilingualGeneratorError {
     #[error("Invalid Bilingual Set Format: {0}")]
     InvalidBilingualSet(String),
 
+    // 一次列出 bilingual set 中的所有问题
+    #[error("Invalid bilingual set {file}:{}", format_issues(.issues))]
+    BilingualSetIssues { file: String, issues: Vec<SetIssue> },
+
     #[error("Failed to extract PAK files")]
     PakExtractionFailed,
 
@@ -17,9 +38,24 @@ pub enum BilingualGeneratorError {
         source: io::Error, // 底层 IO 错误
     },
 
+    #[error("Invalid combine rules: {0}")]
+    InvalidRuleSet(String),
+
+    #[error("Invalid overrides: {0}")]
+    InvalidOverrides(String),
+
+    #[error("Invalid decision log: {0}")]
+    InvalidDecisionLog(String),
+
+    #[error("Calibration failed: {0}")]
+    CalibrationFailed(String),
+
     #[error("XML processing failed: {0}")]
     XmlProcessingFailed(String),
 
+    #[error("Generated XML failed validation: {file} entry '{entry_id}': {reason}")]
+    XmlValidationFailed { file: String, entry_id: String, reason: String },
+
     #[error("Failed to create new PAK file")]
     PakCreationFailed, // 这个可能也需要细化，但目前只处理读取部分
 
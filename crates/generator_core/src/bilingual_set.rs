@@ -0,0 +1,637 @@
+use super::{
+    bilingual_generator_errors::{BilingualGeneratorError, SetIssue},
+    combine_rules::{Separators, Styles, Templates, TextOrder},
+};
+use faststr::FastStr;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    fmt,
+    path::{Path, PathBuf},
+};
+use toml::Spanned;
+
+pub static BILINGUAL_SET_TOML: &str = "bilingual_set.toml";
+pub static BILINGUAL_SET_TXT: &str = "bilingual_set.txt";
+// Used for entries missing from the secondary language unless a pair names another one
+pub static DEFAULT_FALLBACK_LANGUAGE: &str = "English";
+
+/// One pair of the bilingual set: a primary language followed by any number of extra languages.
+///
+/// In `bilingual_set.txt`:
+/// `Chineses + English`             primary + secondary, written to `Chineses_xml.pak`
+/// `Chineses + English + German`    primary + two extra languages, in that order
+/// `Chineses + Japanese -> English` same, but written to the English slot (`English_xml.pak`)
+/// `Japanese -> English`            pure language swap: Japanese text in the English slot
+///
+/// `bilingual_set.toml` additionally sets the per-pair options below.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BilingualPair {
+    pub primary: FastStr,
+    /// Extra languages in display order; empty for a language swap.
+    pub secondaries: Vec<FastStr>,
+    pub target_slot: Option<FastStr>,
+    /// Overrides the separators of the pair's rule set.
+    pub separators: Option<Separators>,
+    /// Overrides the `slash` and `newline` templates of the pair's rule set.
+    pub templates: Option<Templates>,
+    /// Overrides the markup styles of the pair's rule set.
+    pub styles: Option<Styles>,
+    /// Base language and text order of some files, the first matching entry wins.
+    pub file_options: Vec<FileOptions>,
+    /// Globs of the XML files to generate; empty means every XML file of the primary pak.
+    pub include: Vec<String>,
+    /// Globs of XML files to leave out, applied after `include`.
+    pub exclude: Vec<String>,
+    /// Languages tried in order when the secondary text is missing; English if unset,
+    /// an empty chain leaves the primary text alone.
+    pub fallback: Option<Vec<FastStr>>,
+    /// Name of the rule set in `assets/rules` or of a preset; unset or `default` picks the
+    /// generator's rule set, or the preset for the primary language's script.
+    pub rule_set: Option<String>,
+    /// Name of the output folder under `bilingual_xml`.
+    pub output_name: Option<String>,
+}
+
+/// One `[[pair]]` table of `bilingual_set.toml`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PairConfig {
+    pub primary: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secondary: Option<LanguageList>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_slot: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<LanguageList>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule_set: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_name: Option<String>,
+    // Tables go last so the serialized TOML stays valid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub separators: Option<Separators>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub templates: Option<Templates>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub styles: Option<Styles>,
+    #[serde(rename = "file", default, skip_serializing_if = "Vec::is_empty")]
+    pub file_options: Vec<FileOptions>,
+}
+
+/// One `[[pair.file]]` table: how some XML files of a pair are combined.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileOptions {
+    /// Globs of the XML files these options apply to.
+    pub files: Vec<String>,
+    /// Language whose text the rules combine with the others, the pair's primary language or
+    /// one of its extra languages; the pair's primary takes the place of an extra language.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<TextOrder>,
+}
+
+/// One language or an ordered list: `secondary = "English"`, `secondary = ["English", "German"]`,
+/// `fallback = ["Japanese", "English"]`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum LanguageList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl LanguageList {
+    pub fn languages(&self) -> &[String] {
+        match self {
+            LanguageList::One(language) => std::slice::from_ref(language),
+            LanguageList::Many(languages) => languages,
+        }
+    }
+
+    fn from_languages(languages: &[FastStr]) -> Self {
+        match languages {
+            [language] => LanguageList::One(language.to_string()),
+            _ => LanguageList::Many(languages.iter().map(|l| l.to_string()).collect()),
+        }
+    }
+}
+
+// Read with `Spanned` pairs, so each pair is reported at its place in the file
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct BilingualSetConfig<P = PairConfig> {
+    #[serde(rename = "pair", default = "Vec::new")]
+    pairs: Vec<P>,
+}
+
+impl BilingualPair {
+    pub fn new(primary: impl Into<FastStr>, secondary: impl Into<FastStr>) -> Self {
+        Self::multilingual(primary, [secondary])
+    }
+
+    /// A pair showing every extra language after the primary, in order.
+    pub fn multilingual<S: Into<FastStr>>(primary: impl Into<FastStr>, secondaries: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            primary: primary.into(),
+            secondaries: secondaries.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_target_slot(mut self, slot: impl Into<FastStr>) -> Self {
+        self.target_slot = Some(slot.into());
+        self
+    }
+
+    /// Parses `Primary + Secondary [+ More...]`, optionally followed by `-> Slot`, or `Primary -> Slot`.
+    pub fn parse_line(line: &str) -> Result<Self, BilingualGeneratorError> {
+        let invalid = || BilingualGeneratorError::InvalidBilingualSet(line.to_string());
+        let (languages, slot) = match line.split_once("->") {
+            Some((languages, slot)) => (languages, Some(slot.trim())),
+            None => (line, None),
+        };
+        if slot.is_some_and(|s| s.is_empty() || s.contains('+')) {
+            return Err(invalid());
+        }
+
+        let parts: Vec<&str> = languages.split('+').map(|s| s.trim()).collect();
+        if parts.iter().any(|p| p.is_empty()) {
+            return Err(invalid());
+        }
+        let pair = match (parts.as_slice(), slot) {
+            ([primary, secondaries @ ..], _) if !secondaries.is_empty() => {
+                BilingualPair::multilingual(primary.to_string(), secondaries.iter().map(|s| s.to_string()))
+            }
+            // A pair without secondary only makes sense when it targets another slot
+            ([primary], Some(slot)) if *primary != slot => Self {
+                primary: primary.to_string().into(),
+                ..Default::default()
+            },
+            _ => return Err(invalid()),
+        };
+        Ok(match slot {
+            Some(slot) => pair.with_target_slot(slot.to_string()),
+            None => pair,
+        })
+    }
+
+    /// In-game language whose pak is replaced.
+    pub fn slot(&self) -> &str {
+        self.target_slot.as_deref().unwrap_or(&self.primary)
+    }
+
+    pub fn pak_name(&self) -> String {
+        format!("{}_xml.pak", self.slot())
+    }
+
+    /// True when the pair only moves the primary language into another slot.
+    pub fn is_language_swap(&self) -> bool {
+        self.secondaries.is_empty()
+    }
+
+    /// Name of the pair's output folder under `bilingual_xml`.
+    pub fn output_name(&self) -> String {
+        if let Some(name) = &self.output_name {
+            return name.clone();
+        }
+        let mut languages = self.primary.to_string();
+        for secondary in &self.secondaries {
+            languages.push_str(" + ");
+            languages.push_str(secondary);
+        }
+        if self.slot() == self.primary {
+            languages
+        } else {
+            format!("{} as {}", languages, self.slot())
+        }
+    }
+
+    /// Fallback chain of the pair; a language swap never falls back.
+    pub fn fallback_languages(&self) -> Vec<&str> {
+        match &self.fallback {
+            _ if self.is_language_swap() => Vec::new(),
+            Some(chain) => chain.iter().map(|l| l.as_str()).collect(),
+            None => vec![DEFAULT_FALLBACK_LANGUAGE],
+        }
+    }
+
+    /// Compiles the pair's `include` and `exclude` globs and those of its `[[pair.file]]` options.
+    pub fn file_filter(&self) -> Result<FileFilter, BilingualGeneratorError> {
+        Ok(FileFilter {
+            include: (!self.include.is_empty()).then(|| build_glob_set(&self.include)).transpose()?,
+            exclude: build_glob_set(&self.exclude)?,
+            file_options: self
+                .file_options
+                .iter()
+                .map(|options| Ok((build_glob_set(&options.files)?, options.clone())))
+                .collect::<Result<_, BilingualGeneratorError>>()?,
+        })
+    }
+
+    /// Languages whose paks have to be read for this pair: primary, extra languages and the fallback chain.
+    /// A language swap never falls back, so it only needs its primary language.
+    pub fn languages(&self) -> Vec<FastStr> {
+        let mut languages = vec![self.primary.clone()];
+        languages.extend(self.secondaries.iter().cloned());
+        for fallback in self.fallback_languages() {
+            if !languages.iter().any(|l| l == fallback) {
+                languages.push(fallback.to_string().into());
+            }
+        }
+        languages
+    }
+
+    /// Message shown once the pair has been generated.
+    pub fn describe(&self) -> String {
+        let mut message = format!(
+            "primary_language = {}, {} = {}",
+            self.primary,
+            if self.secondaries.len() > 1 {
+                "secondary_languages"
+            } else {
+                "secondary_language"
+            },
+            if self.is_language_swap() {
+                "-".to_string()
+            } else {
+                self.secondaries.join(", ")
+            }
+        );
+        if self.slot() != self.primary {
+            message.push_str(&format!(", target_slot = {}", self.slot()));
+        }
+        message
+    }
+}
+
+impl fmt::Display for BilingualPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.primary)?;
+        for secondary in &self.secondaries {
+            write!(f, " + {}", secondary)?;
+        }
+        match &self.target_slot {
+            Some(slot) => write!(f, " -> {}", slot),
+            None if self.is_language_swap() => write!(f, " -> {}", self.slot()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Decides which XML files a pair generates and which `[[pair.file]]` options they get.
+#[derive(Debug, Clone)]
+pub struct FileFilter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    file_options: Vec<(GlobSet, FileOptions)>,
+}
+
+impl FileFilter {
+    pub fn matches(&self, file_name: &str) -> bool {
+        self.include.as_ref().is_none_or(|include| include.is_match(file_name)) && !self.exclude.is_match(file_name)
+    }
+
+    /// The first `[[pair.file]]` options matching `file_name`.
+    pub fn file_options(&self, file_name: &str) -> Option<&FileOptions> {
+        self.file_options
+            .iter()
+            .find(|(globs, _)| globs.is_match(file_name))
+            .map(|(_, options)| options)
+    }
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<GlobSet, BilingualGeneratorError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|e| BilingualGeneratorError::InvalidBilingualSet(format!("invalid glob '{}': {}", pattern, e)))?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|e| BilingualGeneratorError::InvalidBilingualSet(e.to_string()))
+}
+
+impl TryFrom<PairConfig> for BilingualPair {
+    type Error = BilingualGeneratorError;
+
+    fn try_from(config: PairConfig) -> Result<Self, Self::Error> {
+        let invalid = |reason: &str| BilingualGeneratorError::InvalidBilingualSet(format!("pair with primary '{}': {}", config.primary, reason));
+        let non_empty = |value: &Option<String>| value.as_deref().is_none_or(|v| !v.trim().is_empty());
+        if config.primary.trim().is_empty() {
+            return Err(invalid("primary must not be empty"));
+        }
+        let secondaries = config.secondary.as_ref().map_or(&[][..], LanguageList::languages);
+        let fallback_chain = config.fallback.as_ref().map_or(&[][..], LanguageList::languages);
+        if ![&config.target_slot, &config.rule_set, &config.output_name].into_iter().all(non_empty)
+            || secondaries.iter().chain(fallback_chain).any(|l| l.trim().is_empty())
+        {
+            return Err(invalid("options must not be empty strings"));
+        }
+        if secondaries.is_empty() && config.target_slot.as_deref().is_none_or(|slot| slot == config.primary) {
+            return Err(invalid("a pair without secondary needs a target_slot other than its primary"));
+        }
+        // Both end up in paths, so they must stay plain names
+        let is_plain_name = |value: &Option<String>| value.as_deref().is_none_or(|v| !v.contains(['/', '\\']) && v != ".." && v != ".");
+        if !is_plain_name(&config.rule_set) || !is_plain_name(&config.output_name) {
+            return Err(invalid("rule_set and output_name must be plain names, not paths"));
+        }
+        if let Some(templates) = &config.templates {
+            templates.validate().map_err(|e| invalid(&e))?;
+        }
+        if let Some(styles) = &config.styles {
+            styles.validate().map_err(|e| invalid(&e))?;
+        }
+        for options in &config.file_options {
+            if options.files.is_empty() || (options.base.is_none() && options.order.is_none()) {
+                return Err(invalid("a [[pair.file]] needs files and a base or an order"));
+            }
+            if secondaries.is_empty() {
+                return Err(invalid("a pair without secondary has no [[pair.file]] options"));
+            }
+            if let Some(base) = &options.base
+                && base != config.primary.trim()
+                && !secondaries.iter().any(|l| l.trim() == base)
+            {
+                return Err(invalid(&format!("base '{}' is not one of the pair's languages", base)));
+            }
+            build_glob_set(&options.files).map_err(|e| invalid(&issue_message(e)))?;
+        }
+        build_glob_set(&config.include).map_err(|e| invalid(&issue_message(e)))?;
+        build_glob_set(&config.exclude).map_err(|e| invalid(&issue_message(e)))?;
+        Ok(Self {
+            primary: config.primary.trim().to_string().into(),
+            secondaries: secondaries.iter().map(|l| l.trim().to_string().into()).collect(),
+            target_slot: config.target_slot.map(|s| s.trim().to_string().into()),
+            separators: config.separators,
+            templates: config.templates,
+            styles: config.styles,
+            file_options: config.file_options,
+            include: config.include,
+            exclude: config.exclude,
+            fallback: config
+                .fallback
+                .map(|fallback| fallback.languages().iter().map(|l| l.trim().to_string().into()).collect()),
+            rule_set: config.rule_set,
+            output_name: config.output_name,
+        })
+    }
+}
+
+impl From<&BilingualPair> for PairConfig {
+    fn from(pair: &BilingualPair) -> Self {
+        Self {
+            primary: pair.primary.to_string(),
+            secondary: (!pair.secondaries.is_empty()).then(|| LanguageList::from_languages(&pair.secondaries)),
+            target_slot: pair.target_slot.as_ref().map(|s| s.to_string()),
+            include: pair.include.clone(),
+            exclude: pair.exclude.clone(),
+            fallback: pair
+                .fallback
+                .as_ref()
+                .map(|chain| LanguageList::Many(chain.iter().map(|l| l.to_string()).collect())),
+            rule_set: pair.rule_set.clone(),
+            output_name: pair.output_name.clone(),
+            separators: pair.separators.clone(),
+            templates: pair.templates.clone(),
+            styles: pair.styles.clone(),
+            file_options: pair.file_options.clone(),
+        }
+    }
+}
+
+/// A bilingual set file: every pair with the line it was read from, plus everything wrong with it.
+/// Issues are collected instead of stopping at the first one, so one run reports every mistake.
+#[derive(Debug, Clone, Default)]
+pub struct BilingualSetFile {
+    pub file: String,
+    pub entries: Vec<(usize, BilingualPair)>,
+    pub issues: Vec<SetIssue>,
+}
+
+impl BilingualSetFile {
+    /// Parses the line based `bilingual_set.txt`, empty lines are skipped.
+    pub fn from_txt(file: &str, content: &str) -> Self {
+        let mut set = Self {
+            file: file.to_string(),
+            ..Default::default()
+        };
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            match BilingualPair::parse_line(line) {
+                Ok(pair) => set.entries.push((index + 1, pair)),
+                Err(_) => set.issue(
+                    index + 1,
+                    format!(
+                        "expected `Primary + Secondary`, `Primary + Secondary -> Slot` or `Primary -> Slot`, found '{}'",
+                        line
+                    ),
+                ),
+            }
+        }
+        set.check_pairs();
+        set
+    }
+
+    /// Parses `bilingual_set.toml`, a list of `[[pair]]` tables.
+    pub fn from_toml(file: &str, content: &str) -> Self {
+        let mut set = Self {
+            file: file.to_string(),
+            ..Default::default()
+        };
+        let config: BilingualSetConfig<Spanned<PairConfig>> = match toml::from_str(content) {
+            Ok(config) => config,
+            Err(e) => {
+                let line = e.span().map_or(1, |span| line_of_offset(content, span.start));
+                set.issue(line, e.message().to_string());
+                return set;
+            }
+        };
+        for pair_config in config.pairs {
+            let line = line_of_offset(content, pair_config.span().start);
+            match BilingualPair::try_from(pair_config.into_inner()) {
+                Ok(pair) => set.entries.push((line, pair)),
+                Err(e) => set.issue(line, issue_message(e)),
+            }
+        }
+        set.check_pairs();
+        set
+    }
+
+    fn issue(&mut self, line: usize, message: String) {
+        self.issues.push(SetIssue { line, message });
+    }
+
+    // Problems visible without the game files: `X + X` pairs and pairs writing the same pak
+    fn check_pairs(&mut self) {
+        let mut issues = Vec::new();
+        let mut outputs: HashMap<(String, String), usize> = HashMap::new();
+        for (line, pair) in &self.entries {
+            let shown: Vec<&FastStr> = std::iter::once(&pair.primary).chain(&pair.secondaries).collect();
+            if let Some(repeated) = shown.iter().enumerate().find_map(|(i, l)| shown[..i].contains(l).then_some(l)) {
+                issues.push(SetIssue {
+                    line: *line,
+                    message: format!("'{}' pairs {} with itself", pair, repeated),
+                });
+            }
+            match outputs.entry((pair.output_name(), pair.pak_name())) {
+                Entry::Occupied(first) => issues.push(SetIssue {
+                    line: *line,
+                    message: format!("'{}' duplicates the pair on line {}", pair, first.get()),
+                }),
+                Entry::Vacant(slot) => {
+                    slot.insert(*line);
+                }
+            }
+        }
+        self.issues.extend(issues);
+    }
+
+    /// Checks every language the set names against the installed languages,
+    /// suggesting the closest installed name for typos.
+    pub fn check_languages(&mut self, installed_languages: &[String]) {
+        let mut issues = Vec::new();
+        for (line, pair) in &self.entries {
+            let mut named = vec![("primary", Some(pair.primary.as_str()))];
+            named.extend(pair.secondaries.iter().map(|l| ("secondary", Some(l.as_str()))));
+            named.push(("target_slot", pair.target_slot.as_deref()));
+            named.extend(pair.fallback.iter().flatten().map(|l| ("fallback", Some(l.as_str()))));
+            for (role, language) in named {
+                let Some(language) = language else {
+                    continue;
+                };
+                if installed_languages.iter().any(|l| l == language) {
+                    continue;
+                }
+                let hint = match suggest_language(language, installed_languages) {
+                    Some(suggestion) => format!("did you mean '{}'?", suggestion),
+                    None => format!("installed languages: {}", installed_languages.join(", ")),
+                };
+                issues.push(SetIssue {
+                    line: *line,
+                    message: format!("{} language '{}' has no {}_xml.pak, {}", role, language, language, hint),
+                });
+            }
+        }
+        self.issues.extend(issues);
+    }
+
+    /// Returns the pairs, or every issue found as one line-numbered report.
+    pub fn into_pairs(mut self) -> Result<Vec<BilingualPair>, BilingualGeneratorError> {
+        if self.issues.is_empty() {
+            return Ok(self.entries.into_iter().map(|(_, pair)| pair).collect());
+        }
+        self.issues.sort_by_key(|issue| issue.line);
+        Err(BilingualGeneratorError::BilingualSetIssues {
+            file: self.file,
+            issues: self.issues,
+        })
+    }
+}
+
+fn issue_message(error: BilingualGeneratorError) -> String {
+    match error {
+        BilingualGeneratorError::InvalidBilingualSet(message) => message,
+        other => other.to_string(),
+    }
+}
+
+fn line_of_offset(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+/// Closest installed language within a few edits, compared case-insensitively.
+fn suggest_language<'a>(language: &str, installed_languages: &'a [String]) -> Option<&'a str> {
+    let language = language.to_lowercase();
+    let max_distance = (language.chars().count() / 3).max(2);
+    installed_languages
+        .iter()
+        .map(|installed| (edit_distance(&language, &installed.to_lowercase()), installed))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, installed)| installed.as_str())
+}
+
+// Levenshtein distance over chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Parses the line based `bilingual_set.txt`, empty lines are skipped.
+pub fn parse_bilingual_set_txt(content: &str) -> Result<Vec<BilingualPair>, BilingualGeneratorError> {
+    BilingualSetFile::from_txt(BILINGUAL_SET_TXT, content).into_pairs()
+}
+
+/// Parses `bilingual_set.toml`, a list of `[[pair]]` tables.
+pub fn parse_bilingual_set_toml(content: &str) -> Result<Vec<BilingualPair>, BilingualGeneratorError> {
+    BilingualSetFile::from_toml(BILINGUAL_SET_TOML, content).into_pairs()
+}
+
+pub fn bilingual_set_to_toml(pairs: &[BilingualPair]) -> Result<String, BilingualGeneratorError> {
+    let config = BilingualSetConfig {
+        pairs: pairs.iter().map(PairConfig::from).collect(),
+    };
+    toml::to_string(&config).map_err(|e| BilingualGeneratorError::InvalidBilingualSet(e.to_string()))
+}
+
+/// Reads `assets/bilingual_set.toml`, or the older `assets/bilingual_set.txt` if there is no TOML file.
+pub fn read_bilingual_set(working_dir: &Path) -> Result<BilingualSetFile, BilingualGeneratorError> {
+    let assets = working_dir.join("assets");
+    let toml_path = assets.join(BILINGUAL_SET_TOML);
+    let txt_path = assets.join(BILINGUAL_SET_TXT);
+    let path = if toml_path.is_file() {
+        toml_path
+    } else if txt_path.is_file() {
+        txt_path
+    } else {
+        return Err(BilingualGeneratorError::InvalidBilingualSet(format!(
+            "No {} or {} in {:?}",
+            BILINGUAL_SET_TOML, BILINGUAL_SET_TXT, assets
+        )));
+    };
+    let content = std::fs::read_to_string(&path).map_err(|e| BilingualGeneratorError::InvalidBilingualSet(format!("{}: {}", path.display(), e)))?;
+    let file = path.display().to_string();
+    if path.extension().is_some_and(|ext| ext == "toml") {
+        Ok(BilingualSetFile::from_toml(&file, &content))
+    } else {
+        Ok(BilingualSetFile::from_txt(&file, &content))
+    }
+}
+
+/// Loads the bilingual set without checking its languages against a game install.
+pub fn load_bilingual_set(working_dir: &Path) -> Result<Vec<BilingualPair>, BilingualGeneratorError> {
+    read_bilingual_set(working_dir)?.into_pairs()
+}
+
+/// Converts `assets/bilingual_set.txt` into `assets/bilingual_set.toml` and returns the new file.
+/// An existing TOML file is never overwritten.
+pub fn migrate_bilingual_set(working_dir: &Path) -> Result<PathBuf, BilingualGeneratorError> {
+    let assets = working_dir.join("assets");
+    let toml_path = assets.join(BILINGUAL_SET_TOML);
+    let txt_path = assets.join(BILINGUAL_SET_TXT);
+    if toml_path.exists() {
+        return Err(BilingualGeneratorError::InvalidBilingualSet(format!("{} already exists", toml_path.display())));
+    }
+    let content =
+        std::fs::read_to_string(&txt_path).map_err(|e| BilingualGeneratorError::InvalidBilingualSet(format!("{}: {}", txt_path.display(), e)))?;
+    let pairs = parse_bilingual_set_txt(&content)?;
+    std::fs::write(&toml_path, bilingual_set_to_toml(&pairs)?)?;
+    Ok(toml_path)
+}
@@ -0,0 +1,332 @@
+use super::{
+    bilingual_generator::XmlFile,
+    bilingual_generator_errors::BilingualGeneratorError,
+    combine_rules::RuleSet,
+    combiner::LanguageTable,
+    text_metrics::LengthMetric,
+    util::{SEPARATOR_SLASH, read_language_pak},
+};
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+use toml::{Table, Value};
+
+/// Files whose rules were tuned by hand with length cut-offs.
+pub static CALIBRATED_FILES: [&str; 3] = ["text_ui_menus.xml", "text_ui_items.xml", "text_ui_soul.xml"];
+
+// Conditions that pick entries by id, rules with one are kept when calibrating
+static ID_CONDITIONS: [&str; 5] = ["ids", "id_prefix", "id_contains", "id_contains_any", "id_regex"];
+
+// Percentiles listed in the report
+static REPORTED_PERCENTILES: [u8; 5] = [50, 75, 90, 95, 100];
+
+/// Share of entries, in percent, that should end up as "slash" and "newline";
+/// the rest is written primary only.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CalibrationTargets {
+    pub slash: f64,
+    pub newline: f64,
+}
+
+impl Default for CalibrationTargets {
+    fn default() -> Self {
+        Self { slash: 60.0, newline: 30.0 }
+    }
+}
+
+impl CalibrationTargets {
+    pub fn validate(&self) -> Result<(), BilingualGeneratorError> {
+        let in_range = |value: f64| (0.0..=100.0).contains(&value);
+        if !in_range(self.slash) || !in_range(self.newline) || self.slash + self.newline > 100.0 {
+            return Err(BilingualGeneratorError::CalibrationFailed(format!(
+                "slash ({}%) and newline ({}%) must be between 0 and 100 and add up to at most 100",
+                self.slash, self.newline
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Length distribution of one file: the text length at each percentile, nearest rank.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LengthStats {
+    pub count: usize,
+    pub percentiles: Vec<(u8, usize)>,
+}
+
+impl LengthStats {
+    // `sorted` must be sorted ascending
+    fn from_sorted(sorted: &[usize]) -> Self {
+        Self {
+            count: sorted.len(),
+            percentiles: REPORTED_PERCENTILES.iter().map(|p| (*p, percentile(sorted, *p as f64))).collect(),
+        }
+    }
+}
+
+/// Length of the `p`th percentile of `sorted` (ascending), nearest rank; 0 when empty.
+pub fn percentile(sorted: &[usize], p: f64) -> usize {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Thresholds proposed for one file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileCalibration {
+    pub file: String,
+    pub primary: LengthStats,
+    /// Length of "primary/secondary".
+    pub combined: LengthStats,
+    /// Longest primary text written with "slash", `None` if no entry should be.
+    pub slash_max_len: Option<usize>,
+    /// Longest primary text written with "newline", `None` if no entry should be.
+    pub newline_max_len: Option<usize>,
+    /// Resulting share of slash, newline and primary-only entries in percent. Entries of
+    /// equal length get the same action, so these can differ from the targets.
+    pub shares: [f64; 3],
+}
+
+impl FileCalibration {
+    /// Calibrates one file from the texts both languages have.
+    pub fn compute(file: &str, primary: &LanguageTable, secondary: &LanguageTable, metric: LengthMetric, targets: CalibrationTargets) -> Self {
+        let mut primary_lens = Vec::with_capacity(primary.len());
+        let mut combined_lens = Vec::with_capacity(primary.len());
+        for (entry_id, primary_text) in primary {
+            let Some(secondary_text) = secondary.get(entry_id).filter(|t| !t.0.is_empty() && t.0 != "MISSING") else {
+                continue;
+            };
+            primary_lens.push(metric.measure(&primary_text.0));
+            combined_lens.push(metric.measure(&format!("{}{}{}", primary_text.0, SEPARATOR_SLASH, secondary_text.0)));
+        }
+        primary_lens.sort_unstable();
+        combined_lens.sort_unstable();
+
+        let threshold = |share: f64| (share > 0.0 && !primary_lens.is_empty()).then(|| percentile(&primary_lens, share));
+        let slash_max_len = threshold(targets.slash);
+        let newline_max_len = threshold(targets.slash + targets.newline);
+
+        let count = |max_len: Option<usize>| max_len.map_or(0, |max| primary_lens.iter().filter(|len| **len <= max).count());
+        let (slash, up_to_newline) = (count(slash_max_len), count(newline_max_len).max(count(slash_max_len)));
+        let share = |n: usize| {
+            if primary_lens.is_empty() {
+                0.0
+            } else {
+                n as f64 * 100.0 / primary_lens.len() as f64
+            }
+        };
+        Self {
+            file: file.to_string(),
+            primary: LengthStats::from_sorted(&primary_lens),
+            combined: LengthStats::from_sorted(&combined_lens),
+            slash_max_len,
+            newline_max_len,
+            shares: [share(slash), share(up_to_newline - slash), share(primary_lens.len() - up_to_newline)],
+        }
+    }
+}
+
+/// Proposed thresholds for every calibrated file of one language pair.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calibration {
+    pub primary: String,
+    pub secondary: String,
+    pub metric: LengthMetric,
+    pub targets: CalibrationTargets,
+    pub files: Vec<FileCalibration>,
+}
+
+impl Calibration {
+    /// Human readable percentiles and thresholds.
+    pub fn report(&self) -> String {
+        let mut report = format!(
+            "Calibration of {} + {} ({} metric), target {:.0}% slash / {:.0}% newline / {:.0}% primary only\n",
+            self.primary,
+            self.secondary,
+            self.metric.name(),
+            self.targets.slash,
+            self.targets.newline,
+            100.0 - self.targets.slash - self.targets.newline
+        );
+        let percentiles = |stats: &LengthStats| stats.percentiles.iter().map(|(p, len)| format!("p{}={}", p, len)).collect::<Vec<_>>().join(" ");
+        for file in &self.files {
+            let max_len = |len: Option<usize>| len.map_or("-".to_string(), |len| len.to_string());
+            let _ = writeln!(report, "\n{} ({} entries)", file.file, file.primary.count);
+            let _ = writeln!(report, "  primary length:  {}", percentiles(&file.primary));
+            let _ = writeln!(report, "  combined length: {}", percentiles(&file.combined));
+            let _ = writeln!(
+                report,
+                "  slash up to {}, newline up to {} -> {:.1}% / {:.1}% / {:.1}%",
+                max_len(file.slash_max_len),
+                max_len(file.newline_max_len),
+                file.shares[0],
+                file.shares[1],
+                file.shares[2]
+            );
+        }
+        report
+    }
+
+    /// `base` with the proposed thresholds: the rules of every calibrated file that pick entries
+    /// by id are kept as they are, its length rules become "slash", then "newline", then primary
+    /// only. Dialog, the other files and the rest of `base` are copied unchanged.
+    pub fn to_rule_set_toml(&self, base: &RuleSet) -> Result<String, BilingualGeneratorError> {
+        let invalid = |e: String| BilingualGeneratorError::CalibrationFailed(format!("cannot read the active rule set: {}", e));
+        let mut rule_set: Table = base.source.parse().map_err(|e: toml::de::Error| invalid(e.to_string()))?;
+        let base_rules = match rule_set.remove("rule") {
+            Some(Value::Array(rules)) => rules,
+            Some(_) => return Err(invalid("`rule` is not an array of tables".to_string())),
+            None => Vec::new(),
+        };
+        // A file's calibrated rules take the place of its last rule, files without one of their
+        // own go before the first rule for every file
+        let catch_all = base_rules.iter().position(|rule| rule.get("files").is_none()).unwrap_or(base_rules.len());
+        let slots: Vec<(usize, &FileCalibration)> = self
+            .files
+            .iter()
+            .map(|file| {
+                let last = base_rules.iter().rposition(|rule| rule_files(rule).contains(&file.file.as_str()));
+                (last.map_or(catch_all, |index| index + 1), file)
+            })
+            .collect();
+        let calibrated_at = |index: usize| {
+            slots
+                .iter()
+                .filter(move |(slot, _)| *slot == index)
+                .flat_map(|(_, file)| self.length_rules(file))
+        };
+
+        let mut rules = Vec::with_capacity(base_rules.len());
+        for (index, rule) in base_rules.iter().enumerate() {
+            rules.extend(calibrated_at(index));
+            rules.extend(self.kept_rule(rule));
+        }
+        rules.extend(calibrated_at(base_rules.len()));
+        rule_set.insert("rule".to_string(), Value::Array(rules));
+
+        let body = toml::to_string(&rule_set).map_err(|e| invalid(e.to_string()))?;
+        Ok(format!(
+            "# Calibrated for {} + {}: {:.0}% slash / {:.0}% newline / {:.0}% primary only, measured in {}.\n\
+             # Generated by `kcd_bilingual_generator_rust calibrate` from the {} rules, see\n\
+             # assets/rules/presets/cjk.toml for the rule format.\n\n{}",
+            self.primary,
+            self.secondary,
+            self.targets.slash,
+            self.targets.newline,
+            100.0 - self.targets.slash - self.targets.newline,
+            self.metric.name(),
+            base.preset.unwrap_or("user's"),
+            body
+        ))
+    }
+
+    // "slash" and "newline" up to the file's thresholds, then primary only
+    fn length_rules(&self, file: &FileCalibration) -> Vec<Value> {
+        let key = file_key(&file.file);
+        let rule = |name: String, max_len: Option<usize>, action: &str| {
+            let mut rule = Table::new();
+            rule.insert("name".to_string(), Value::from(name));
+            rule.insert("files".to_string(), Value::from(vec![file.file.clone()]));
+            if let Some(max_len) = max_len {
+                rule.insert("max_primary_len".to_string(), Value::from(max_len as i64));
+                rule.insert("length_metric".to_string(), Value::from(self.metric.name()));
+            }
+            rule.insert("action".to_string(), Value::from(action));
+            Value::Table(rule)
+        };
+        let mut rules = Vec::with_capacity(3);
+        if let Some(max_len) = file.slash_max_len {
+            rules.push(rule(format!("{}_slash", key), Some(max_len), "slash"));
+        }
+        // A newline threshold no longer than the slash one would never match
+        if let Some(max_len) = file.newline_max_len.filter(|len| file.slash_max_len.is_none_or(|slash| *len > slash)) {
+            rules.push(rule(format!("{}_newline", key), Some(max_len), "newline"));
+        }
+        rules.push(rule(key.to_string(), None, "primary_only"));
+        rules
+    }
+
+    // A rule of the active rule set as it is, without the calibrated files unless it picks
+    // entries by id; `None` when only calibrated files are left
+    fn kept_rule(&self, rule: &Value) -> Option<Value> {
+        let files = rule_files(rule);
+        let by_id = ID_CONDITIONS.iter().any(|condition| rule.get(condition).is_some());
+        let calibrated = |file: &&str| self.files.iter().any(|calibrated| calibrated.file == *file);
+        if by_id || !files.iter().any(calibrated) {
+            return Some(rule.clone());
+        }
+        let others: Vec<&str> = files.into_iter().filter(|file| !calibrated(file)).collect();
+        if others.is_empty() {
+            return None;
+        }
+        let mut rule = rule.clone();
+        rule.as_table_mut()?.insert("files".to_string(), Value::from(others));
+        Some(rule)
+    }
+
+    /// Writes the rule set calibrated from `base` to `assets/rules/<name>.toml`, never over an existing file.
+    pub fn write_rule_set(&self, working_dir: &Path, name: &str, base: &RuleSet) -> Result<PathBuf, BilingualGeneratorError> {
+        if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+            return Err(BilingualGeneratorError::CalibrationFailed(format!("'{}' is not a plain rule set name", name)));
+        }
+        let rules_dir = working_dir.join("assets").join("rules");
+        let path = rules_dir.join(format!("{}.toml", name));
+        if path.exists() {
+            return Err(BilingualGeneratorError::CalibrationFailed(format!("{} already exists", path.display())));
+        }
+        let toml = self.to_rule_set_toml(base)?;
+        std::fs::create_dir_all(&rules_dir)?;
+        std::fs::write(&path, toml)?;
+        Ok(path)
+    }
+}
+
+// The files a rule is limited to, none for a rule that applies to every file
+fn rule_files(rule: &Value) -> Vec<&str> {
+    rule.get("files")
+        .and_then(Value::as_array)
+        .map_or_else(Vec::new, |files| files.iter().filter_map(Value::as_str).collect())
+}
+
+// "text_ui_menus.xml" -> "menus"
+fn file_key(file: &str) -> &str {
+    let stem = file.strip_suffix(".xml").unwrap_or(file);
+    stem.strip_prefix("text_ui_").unwrap_or(stem)
+}
+
+/// Reads `files` of both languages from the game's paks and calibrates each file.
+pub fn calibrate(
+    game_path: &Path,
+    primary: &str,
+    secondary: &str,
+    files: &[String],
+    metric: LengthMetric,
+    targets: CalibrationTargets,
+) -> Result<Calibration, BilingualGeneratorError> {
+    targets.validate()?;
+    let primary_data = read_language_pak(game_path, primary, files)?;
+    let secondary_data = read_language_pak(game_path, secondary, files)?;
+    let files = files
+        .iter()
+        .filter_map(|file| {
+            let xml_file = XmlFile(file.clone().into());
+            Some(FileCalibration::compute(
+                file,
+                primary_data.get(&xml_file)?,
+                secondary_data.get(&xml_file)?,
+                metric,
+                targets,
+            ))
+        })
+        .collect();
+    Ok(Calibration {
+        primary: primary.to_string(),
+        secondary: secondary.to_string(),
+        metric,
+        targets,
+        files,
+    })
+}
@@ -0,0 +1,643 @@
+use super::{
+    bilingual_generator::{EntryId, LastTextValue},
+    bilingual_generator_errors::BilingualGeneratorError,
+    normalization::Normalization,
+    sentence_alignment::{align_sentences, first_sentence},
+    text_metrics::{FontMetrics, LengthMetric, Script, rendered_width, truncate_to_width},
+    tokens::strip_markup,
+    util::{SEPARATOR_NEWLINE, SEPARATOR_SLASH, texts_combined, texts_combined_secondary_first},
+};
+use faststr::FastStr;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::{borrow::Cow, collections::BTreeMap};
+
+// `{secondary_N}` in templates, N counting the extra languages from 1
+static NUMBERED_SECONDARY: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{secondary_(\d+)\}").expect("valid regex"));
+
+// Any `{...}` in a template
+static PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{([^{}]*)\}").expect("valid regex"));
+
+// A markup tag in a style: closing slash, name, self-closing slash
+static MARKUP_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<(/?)([A-Za-z][A-Za-z0-9]*)[^<>]*?(/?)>").expect("valid regex"));
+
+// Built-in rule set, reproduces the original hardcoded combine logic.
+pub static DEFAULT_RULES: &str = include_str!("../../../assets/rules/presets/cjk.toml");
+
+/// Built-in rule presets by name. `cjk` is the default rule set; `cjk`, `latin` and
+/// `cyrillic` are picked from the primary language's script, `learner` only on request.
+pub static PRESETS: [(&str, &str); 4] = [
+    ("cjk", DEFAULT_RULES),
+    ("latin", include_str!("../../../assets/rules/presets/latin.toml")),
+    ("cyrillic", include_str!("../../../assets/rules/presets/cyrillic.toml")),
+    ("learner", include_str!("../../../assets/rules/presets/learner.toml")),
+];
+
+/// Name of the preset for a primary language written in `script`.
+pub fn preset_for_script(script: Script) -> &'static str {
+    match script {
+        Script::Cjk => "cjk",
+        Script::Latin => "latin",
+        Script::Cyrillic => "cyrillic",
+    }
+}
+
+/// What to do with an entry once a rule matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CombineAction {
+    PrimaryOnly,
+    Slash,
+    Newline,
+    Template,
+}
+
+/// Whether the primary text or the extra languages come first in the `slash` and `newline`
+/// actions. Templates place the texts themselves.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextOrder {
+    #[default]
+    PrimaryFirst,
+    SecondaryFirst,
+}
+
+impl TextOrder {
+    /// Joins the primary text and the extra texts, each extra text with the separator before it.
+    pub fn combine<'a>(
+        self,
+        primary_text: &LastTextValue,
+        extras: impl IntoIterator<Item = (&'a str, &'a str)>,
+        normalization: &Normalization,
+    ) -> FastStr {
+        match self {
+            TextOrder::PrimaryFirst => texts_combined(primary_text, extras, normalization),
+            TextOrder::SecondaryFirst => texts_combined_secondary_first(primary_text, extras, normalization),
+        }
+    }
+}
+
+/// A single rule: every condition that is set must hold for the rule to match.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CombineRule {
+    pub name: String,
+    #[serde(default)]
+    pub files: Vec<String>,
+    #[serde(default)]
+    pub ids: Vec<String>,
+    #[serde(default)]
+    pub id_prefix: Vec<String>,
+    #[serde(default)]
+    pub id_contains: Vec<String>,
+    #[serde(default)]
+    pub id_contains_any: Vec<String>,
+    #[serde(default)]
+    pub id_not_contains: Vec<String>,
+    #[serde(default, rename = "id_regex")]
+    pub id_regex_source: Option<String>,
+    #[serde(skip)]
+    pub id_regex: Option<Regex>,
+    pub min_primary_len: Option<usize>,
+    pub max_primary_len: Option<usize>,
+    pub min_secondary_len: Option<usize>,
+    pub max_secondary_len: Option<usize>,
+    /// How the length conditions measure a text, `chars` or display `width`.
+    #[serde(default)]
+    pub length_metric: LengthMetric,
+    /// The combined text must fit in this many pixels, measured with the rule set's font.
+    /// Lets rules fall through, e.g. slash if it fits, else newline, else primary only.
+    pub max_width_px: Option<f32>,
+    /// Font size in pixels per em for `max_width_px`, defaults to the rule set's `[font]` size.
+    pub font_size: Option<f32>,
+    pub action: CombineAction,
+    pub template: Option<String>,
+    /// How many of the pair's extra languages to show, all of them if unset.
+    pub max_extra: Option<usize>,
+    /// Separator before each extra language in turn, the last one repeats.
+    /// Defaults to the rule set's separator of the action.
+    #[serde(default)]
+    pub separators: Vec<String>,
+    /// Use the fallback language when the secondary text is missing.
+    #[serde(default)]
+    pub fallback: bool,
+    /// Interleave the sentences of the primary and the secondary text, each pair on its own
+    /// line, when both split into the same number of sentences of similar length; otherwise
+    /// the texts are combined whole. Only for a single extra language.
+    #[serde(default)]
+    pub align_sentences: bool,
+    /// Show only the first sentence of each extra text, ending in `…`.
+    #[serde(default)]
+    pub abbreviate_to_sentence: bool,
+    /// Cut each extra text to this many display columns, ending in `…`.
+    pub abbreviate_to_width: Option<usize>,
+    /// Remove the markup tags of each extra text; the rule set's styles are still applied.
+    #[serde(default)]
+    pub strip_markup: bool,
+}
+
+/// Separators used by the `slash` and `newline` actions.
+/// Set by a `[separators]` table in a rule set, or per pair in the bilingual set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Separators {
+    pub slash: String,
+    pub newline: String,
+}
+
+impl Default for Separators {
+    fn default() -> Self {
+        Self {
+            slash: SEPARATOR_SLASH.to_string(),
+            newline: SEPARATOR_NEWLINE.to_string(),
+        }
+    }
+}
+
+/// Templates replacing the separator of the `slash` and `newline` actions, e.g.
+/// `{primary}（{secondary}）` or `{secondary}\\n{primary}`.
+/// Set by a `[templates]` table in a rule set, or per pair in the bilingual set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Templates {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub newline: Option<String>,
+}
+
+impl Templates {
+    pub fn validate(&self) -> Result<(), String> {
+        for (action, template) in [("slash", &self.slash), ("newline", &self.newline)] {
+            if let Some(template) = template {
+                validate_template(template).map_err(|e| format!("{} template: {}", action, e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks that a template shows the primary and at least one extra language, uses no unknown
+/// placeholder and only contains characters allowed in XML.
+pub fn validate_template(template: &str) -> Result<(), String> {
+    for caps in PLACEHOLDER.captures_iter(template) {
+        let name = &caps[1];
+        let numbered = name.strip_prefix("secondary_").and_then(|n| n.parse::<usize>().ok());
+        if name != "primary" && name != "secondary" && numbered.is_none_or(|n| n == 0) {
+            return Err(format!("unknown placeholder {{{}}} in \"{}\"", name, template));
+        }
+    }
+    if !template.contains("{primary}") || !(template.contains("{secondary}") || NUMBERED_SECONDARY.is_match(template)) {
+        return Err(format!("\"{}\" needs both {{primary}} and {{secondary}}", template));
+    }
+    if let Some(c) = invalid_xml_char(template) {
+        return Err(format!("\"{}\" contains {:?}, which is not allowed in XML", template, c));
+    }
+    Ok(())
+}
+
+// XML 1.0 allows no control characters besides tab and line breaks
+fn invalid_xml_char(text: &str) -> Option<char> {
+    text.chars()
+        .find(|c| (*c < ' ' && !matches!(c, '\t' | '\n' | '\r')) || matches!(c, '\u{FFFE}' | '\u{FFFF}'))
+}
+
+/// Markup put around the text of every extra language, by XML file name; `*` applies to
+/// every other file. `{text}` stands for the text, e.g. `<font color="#a0a0a0">{text}</font>`.
+/// Set by a `[styles]` table in a rule set, or per pair in the bilingual set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Styles(pub BTreeMap<String, String>);
+
+impl Styles {
+    pub fn validate(&self) -> Result<(), String> {
+        for (file_name, style) in &self.0 {
+            validate_style(style).map_err(|e| format!("style of {}: {}", file_name, e))?;
+        }
+        Ok(())
+    }
+
+    /// The style of a file, if any.
+    pub fn for_file(&self, file_name: &str) -> Option<&str> {
+        self.0.get(file_name).or_else(|| self.0.get("*")).map(String::as_str)
+    }
+
+    /// Wraps each text of the extra languages in the file's style, missing texts stay as they are.
+    pub fn apply<'a>(&self, file_name: &str, texts: &[&'a str]) -> Vec<Cow<'a, str>> {
+        let style = self.for_file(file_name);
+        texts
+            .iter()
+            .map(|text| match style {
+                Some(style) if *text != "MISSING" && !text.is_empty() => Cow::Owned(style.replace("{text}", text)),
+                _ => Cow::Borrowed(*text),
+            })
+            .collect()
+    }
+}
+
+/// Checks that a style holds `{text}` once, its markup tags are balanced and it only contains
+/// characters allowed in XML.
+pub fn validate_style(style: &str) -> Result<(), String> {
+    if style.matches("{text}").count() != 1 {
+        return Err(format!("\"{}\" needs {{text}} exactly once", style));
+    }
+    let mut open: Vec<&str> = Vec::new();
+    for caps in MARKUP_TAG.captures_iter(style) {
+        let (closing, name, self_closing) = (!caps[1].is_empty(), caps.get(2).map_or("", |m| m.as_str()), !caps[3].is_empty());
+        if self_closing {
+            continue;
+        }
+        if !closing {
+            open.push(name);
+        } else if open.pop() != Some(name) {
+            return Err(format!("\"{}\" closes <{}> without opening it", style, name));
+        }
+    }
+    if let Some(name) = open.last() {
+        return Err(format!("\"{}\" never closes <{}>", style, name));
+    }
+    if let Some(c) = invalid_xml_char(style) {
+        return Err(format!("\"{}\" contains {:?}, which is not allowed in XML", style, c));
+    }
+    Ok(())
+}
+
+/// Fills a template: `{primary}`, `{secondary}` (the first extra language) and `{secondary_N}`.
+/// Placeholders without a text are dropped.
+pub fn render_template(template: &str, primary_text: &str, extra_texts: &[&str]) -> String {
+    let text = template
+        .replace("{primary}", primary_text)
+        .replace("{secondary}", extra_texts.first().copied().unwrap_or(""));
+    NUMBERED_SECONDARY
+        .replace_all(&text, |caps: &regex::Captures| {
+            let index: usize = caps[1].parse().unwrap_or(0);
+            index.checked_sub(1).and_then(|i| extra_texts.get(i)).copied().unwrap_or("").to_string()
+        })
+        .into_owned()
+}
+
+/// Font the `max_width_px` limits are measured with, set by a `[font]` table in a rule set.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FontSettings {
+    /// TTF/OTF file, relative to the working directory. Without it widths are estimated
+    /// from the display width of the text.
+    pub path: Option<PathBuf>,
+    /// Pixels per em.
+    pub size: f32,
+}
+
+impl Default for FontSettings {
+    fn default() -> Self {
+        Self { path: None, size: 24.0 }
+    }
+}
+
+/// Ordered list of rules, the first matching rule wins.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuleSet {
+    #[serde(default)]
+    pub separators: Separators,
+    #[serde(default)]
+    pub templates: Templates,
+    #[serde(default)]
+    pub styles: Styles,
+    #[serde(default)]
+    pub order: TextOrder,
+    #[serde(default, rename = "normalize")]
+    pub normalization: Normalization,
+    #[serde(default)]
+    pub font: FontSettings,
+    /// The loaded `font.path`, see `load_font`.
+    #[serde(skip)]
+    pub font_metrics: Option<FontMetrics>,
+    /// Name of the built-in preset this set was loaded from, `None` for a user's file.
+    /// Only a preset is swapped for the one matching a pair's primary script.
+    #[serde(skip)]
+    pub preset: Option<&'static str>,
+    /// The TOML the set was read from, the starting point of a calibrated rule set.
+    #[serde(skip)]
+    pub source: String,
+    #[serde(rename = "rule", default)]
+    pub rules: Vec<CombineRule>,
+}
+
+impl CombineRule {
+    fn matches(&self, file_name: &str, entry_id: &str, primary_text: &str, secondary_text: Option<&str>) -> bool {
+        if !self.files.is_empty() && !self.files.iter().any(|f| f == file_name) {
+            return false;
+        }
+        if !self.ids.is_empty() && !self.ids.iter().any(|id| id == entry_id) {
+            return false;
+        }
+        if !self.id_prefix.is_empty() && !self.id_prefix.iter().any(|p| entry_id.starts_with(p.as_str())) {
+            return false;
+        }
+        if !self.id_contains.iter().all(|s| entry_id.contains(s.as_str())) {
+            return false;
+        }
+        if !self.id_contains_any.is_empty() && !self.id_contains_any.iter().any(|s| entry_id.contains(s.as_str())) {
+            return false;
+        }
+        if self.id_not_contains.iter().any(|s| entry_id.contains(s.as_str())) {
+            return false;
+        }
+        if self.id_regex.as_ref().is_some_and(|regex| !regex.is_match(entry_id)) {
+            return false;
+        }
+        if self.min_primary_len.is_some() || self.max_primary_len.is_some() {
+            let len = self.length_metric.measure(primary_text);
+            if self.min_primary_len.is_some_and(|min| len < min) || self.max_primary_len.is_some_and(|max| len > max) {
+                return false;
+            }
+        }
+        if self.min_secondary_len.is_some() || self.max_secondary_len.is_some() {
+            // Length conditions on the secondary text never match a missing secondary text
+            let Some(len) = secondary_text.map(|text| self.length_metric.measure(text)) else {
+                return false;
+            };
+            if self.min_secondary_len.is_some_and(|min| len < min) || self.max_secondary_len.is_some_and(|max| len > max) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Texts of the extra languages this rule shows, missing ones replaced by the fallback text.
+    pub fn extra_texts<'a>(&self, secondary_texts: &[Option<&'a str>], fallback_text: Option<&'a str>) -> Vec<&'a str> {
+        extra_texts(secondary_texts, fallback_text.filter(|_| self.fallback), self.max_extra.unwrap_or(usize::MAX))
+    }
+
+    /// Cleans and shortens an extra text as `strip_markup`, `abbreviate_to_sentence` and
+    /// `abbreviate_to_width` ask, in that order.
+    pub fn prepare_extra_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = if self.strip_markup { strip_markup(text) } else { Cow::Borrowed(text) };
+        if self.abbreviate_to_sentence
+            && let Some(sentence) = first_sentence(&text)
+        {
+            text = Cow::Owned(sentence);
+        }
+        if let Some(columns) = self.abbreviate_to_width
+            && let Some(truncated) = truncate_to_width(&text, columns)
+        {
+            text = Cow::Owned(truncated);
+        }
+        text
+    }
+
+    // Separator placed before the extra language at `index`
+    fn separator<'a>(&'a self, index: usize, default: &'a str) -> &'a str {
+        self.separators.get(index).or(self.separators.last()).map_or(default, String::as_str)
+    }
+
+    /// Builds the output text for an entry this rule matched.
+    /// `extra_texts` are the texts of the extra languages that are shown, in order.
+    /// A template of the `slash` or `newline` action wins over the separator unless the rule
+    /// sets its own `separators`; without a template `order` decides which text comes first.
+    /// Every text is tidied up by `normalization`, a primary text shown alone too.
+    pub fn apply(
+        &self,
+        primary_text: &LastTextValue,
+        extra_texts: &[&str],
+        separators: &Separators,
+        templates: &Templates,
+        order: TextOrder,
+        normalization: &Normalization,
+    ) -> FastStr {
+        if self.action == CombineAction::PrimaryOnly {
+            return normalization.primary_text(primary_text).0;
+        }
+        let (normalized_primary, normalized) = normalization.texts(primary_text, extra_texts);
+        let extra_texts: Vec<&str> = normalized.iter().map(AsRef::as_ref).collect();
+        if extra_texts.is_empty() {
+            return normalized_primary.0;
+        }
+        let primary_text = &normalized_primary;
+        let action_template = match self.action {
+            _ if !self.separators.is_empty() => None,
+            CombineAction::Slash => templates.slash.as_deref(),
+            CombineAction::Newline => templates.newline.as_deref(),
+            CombineAction::PrimaryOnly | CombineAction::Template => None,
+        };
+        if let Some(template) = action_template {
+            return render_template(template, &primary_text.0, &extra_texts).into();
+        }
+        match self.action {
+            CombineAction::PrimaryOnly => primary_text.0.clone(),
+            CombineAction::Slash => order.combine(
+                primary_text,
+                extra_texts.iter().enumerate().map(|(i, t)| (self.separator(i, &separators.slash), *t)),
+                normalization,
+            ),
+            CombineAction::Newline => order.combine(
+                primary_text,
+                extra_texts.iter().enumerate().map(|(i, t)| (self.separator(i, &separators.newline), *t)),
+                normalization,
+            ),
+            CombineAction::Template => render_template(self.template.as_deref().unwrap_or("{primary}"), &primary_text.0, &extra_texts).into(),
+        }
+    }
+}
+
+impl RuleSet {
+    pub fn from_toml_str(content: &str) -> Result<Self, BilingualGeneratorError> {
+        let mut rule_set: RuleSet = toml::from_str(content).map_err(|e| BilingualGeneratorError::InvalidRuleSet(e.to_string()))?;
+        rule_set.source = content.to_string();
+        for rule in rule_set.rules.iter_mut() {
+            if let Some(source) = &rule.id_regex_source {
+                let regex = Regex::new(source).map_err(|e| BilingualGeneratorError::InvalidRuleSet(format!("rule '{}': {}", rule.name, e)))?;
+                rule.id_regex = Some(regex);
+            }
+            let positive = |value: Option<f32>| value.is_none_or(|value| value > 0.0);
+            if !positive(rule.max_width_px) || !positive(rule.font_size) {
+                return Err(BilingualGeneratorError::InvalidRuleSet(format!(
+                    "rule '{}': max_width_px and font_size must be positive",
+                    rule.name
+                )));
+            }
+            if rule.abbreviate_to_width == Some(0) {
+                return Err(BilingualGeneratorError::InvalidRuleSet(format!(
+                    "rule '{}': abbreviate_to_width must be positive",
+                    rule.name
+                )));
+            }
+            if rule.align_sentences && rule.action == CombineAction::PrimaryOnly {
+                return Err(BilingualGeneratorError::InvalidRuleSet(format!(
+                    "rule '{}': align_sentences needs an action that shows the secondary text",
+                    rule.name
+                )));
+            }
+            if rule.action == CombineAction::Template {
+                let template = rule
+                    .template
+                    .as_deref()
+                    .ok_or_else(|| BilingualGeneratorError::InvalidRuleSet(format!("rule '{}': action \"template\" needs a template", rule.name)))?;
+                validate_template(template).map_err(|e| BilingualGeneratorError::InvalidRuleSet(format!("rule '{}': {}", rule.name, e)))?;
+            }
+        }
+        rule_set.templates.validate().map_err(BilingualGeneratorError::InvalidRuleSet)?;
+        rule_set.styles.validate().map_err(BilingualGeneratorError::InvalidRuleSet)?;
+        if rule_set.font.size <= 0.0 {
+            return Err(BilingualGeneratorError::InvalidRuleSet("font size must be positive".to_string()));
+        }
+        Ok(rule_set)
+    }
+
+    /// Loads `font.path`, relative to `working_dir`, for the `max_width_px` limits.
+    pub fn load_font(&mut self, working_dir: &Path) -> Result<(), BilingualGeneratorError> {
+        if let Some(path) = &self.font.path {
+            self.font_metrics = Some(FontMetrics::load(&working_dir.join(path))?);
+        }
+        Ok(())
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, BilingualGeneratorError> {
+        let content = std::fs::read_to_string(path).map_err(|e| BilingualGeneratorError::InvalidRuleSet(format!("{}: {}", path.display(), e)))?;
+        Self::from_toml_str(&content).map_err(|e| match e {
+            BilingualGeneratorError::InvalidRuleSet(msg) => BilingualGeneratorError::InvalidRuleSet(format!("{}: {}", path.display(), msg)),
+            other => other,
+        })
+    }
+
+    /// Loads `assets/rules/default.toml` from the working directory if the user created one,
+    /// otherwise the built-in default rules. Only the built-in rules are swapped for the preset
+    /// of a pair's primary script; the shipped presets live in `assets/rules/presets` and are
+    /// never read from disk.
+    pub fn load(working_dir: &Path) -> Result<Self, BilingualGeneratorError> {
+        let user_rules = working_dir.join("assets").join("rules").join("default.toml");
+        let mut rule_set = if user_rules.is_file() {
+            Self::from_file(&user_rules)?
+        } else {
+            Self::default()
+        };
+        rule_set.load_font(working_dir)?;
+        Ok(rule_set)
+    }
+
+    /// Built-in preset by name, see `PRESETS`.
+    pub fn preset(name: &str) -> Option<Self> {
+        let (name, content) = PRESETS.iter().find(|(preset, _)| *preset == name)?;
+        let mut rule_set = Self::from_toml_str(content).expect("built-in rule presets are valid");
+        rule_set.preset = Some(name);
+        Some(rule_set)
+    }
+
+    /// Loads a rule set by name: `default` is `load`, any other name is read from
+    /// `assets/rules/<name>.toml`, or is one of the built-in presets. A pair naming `default`
+    /// gets the preset for its primary script instead, see `pair_rule_set`.
+    pub fn load_named(working_dir: &Path, name: &str) -> Result<Self, BilingualGeneratorError> {
+        if name == "default" {
+            return Self::load(working_dir);
+        }
+        let path = working_dir.join("assets").join("rules").join(format!("{}.toml", name));
+        if !path.is_file() {
+            return Self::preset(name)
+                .ok_or_else(|| BilingualGeneratorError::InvalidRuleSet(format!("rule set '{}' not found at {}", name, path.display())));
+        }
+        let mut rule_set = Self::from_file(&path)?;
+        rule_set.load_font(working_dir)?;
+        Ok(rule_set)
+    }
+
+    // Whether `text` fits the rule's pixel limit, if it has one
+    fn fits(&self, rule: &CombineRule, text: &str) -> bool {
+        rule.max_width_px.is_none_or(|max_width| {
+            let size = rule.font_size.unwrap_or(self.font.size);
+            rendered_width(text, size, self.font_metrics.as_ref()) <= max_width
+        })
+    }
+
+    // The rule's text for the given texts, the extra texts wrapped in the file's style
+    fn combine_texts(&self, rule: &CombineRule, file_name: &str, primary_text: &LastTextValue, extra_texts: &[&str]) -> FastStr {
+        let styled = self.styles.apply(file_name, extra_texts);
+        let extra_texts: Vec<&str> = styled.iter().map(AsRef::as_ref).collect();
+        rule.apply(primary_text, &extra_texts, &self.separators, &self.templates, self.order, &self.normalization)
+    }
+
+    /// Returns the first rule matching the entry together with the text it produces, the extra
+    /// texts wrapped in the file's style. A rule whose text does not fit its `max_width_px` is skipped.
+    pub fn resolve(
+        &self,
+        file_name: &str,
+        entry_id: &EntryId,
+        primary_text: &LastTextValue,
+        secondary_texts: &[Option<&str>],
+        fallback_text: Option<&str>,
+    ) -> Option<(&CombineRule, FastStr)> {
+        let first_secondary = secondary_texts.first().copied().flatten();
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(file_name, &entry_id.0, &primary_text.0, first_secondary))
+            .map(|rule| {
+                let prepared: Vec<Cow<str>> = rule
+                    .extra_texts(secondary_texts, fallback_text)
+                    .into_iter()
+                    .map(|text| {
+                        if text == "MISSING" {
+                            Cow::Borrowed(text)
+                        } else {
+                            rule.prepare_extra_text(text)
+                        }
+                    })
+                    .collect();
+                let extra_texts: Vec<&str> = prepared.iter().map(AsRef::as_ref).collect();
+                let aligned = match extra_texts.as_slice() {
+                    [secondary_text] if rule.align_sentences => align_sentences(&primary_text.0, secondary_text),
+                    _ => None,
+                };
+                let text = match aligned {
+                    Some(sentence_pairs) => sentence_pairs
+                        .into_iter()
+                        .map(|(primary, secondary)| self.combine_texts(rule, file_name, &LastTextValue(FastStr::new(primary)), &[secondary]))
+                        .collect::<Vec<_>>()
+                        .join(&self.separators.newline)
+                        .into(),
+                    None => self.combine_texts(rule, file_name, primary_text, &extra_texts),
+                };
+                (rule, text)
+            })
+            .find(|(rule, text)| self.fits(rule, text))
+    }
+
+    /// Decides the output text for one entry.
+    /// `secondary_texts` holds the text of each extra language of the pair, in order; length
+    /// conditions look at the first one. `fallback_text` is only used by rules with
+    /// `fallback = true` for a missing extra text, and never repeats a text that is already shown.
+    pub fn combine(
+        &self,
+        file_name: &str,
+        entry_id: &EntryId,
+        primary_text: &LastTextValue,
+        secondary_texts: &[Option<&str>],
+        fallback_text: Option<&str>,
+    ) -> FastStr {
+        match self.resolve(file_name, entry_id, primary_text, secondary_texts, fallback_text) {
+            Some((_, text)) => text,
+            None => self.normalization.primary_text(primary_text).0,
+        }
+    }
+}
+
+/// Texts of at most `max_extra` extra languages, in order. A missing text becomes
+/// `fallback_text` (or `MISSING`), and a text that is already shown is not repeated.
+pub fn extra_texts<'a>(secondary_texts: &[Option<&'a str>], fallback_text: Option<&'a str>, max_extra: usize) -> Vec<&'a str> {
+    let mut extra_texts: Vec<&str> = Vec::with_capacity(secondary_texts.len());
+    for text in secondary_texts.iter().take(max_extra) {
+        let text = match text {
+            Some(text) if !text.is_empty() => *text,
+            _ => fallback_text.unwrap_or("MISSING"),
+        };
+        if !extra_texts.contains(&text) {
+            extra_texts.push(text);
+        }
+    }
+    extra_texts
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self::preset("cjk").expect("the default preset exists")
+    }
+}
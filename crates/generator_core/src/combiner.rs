@@ -0,0 +1,363 @@
+use super::{
+    bilingual_generator::{EntryId, LastTextValue, XmlFile},
+    bilingual_generator_errors::BilingualGeneratorError,
+    bilingual_set::BilingualPair,
+    combine_rules::{RuleSet, extra_texts, preset_for_script},
+    decision_log::{DECISION_LOG_CSV, Decision, write_decision_log},
+    overrides::Overrides,
+    pak_builder::PakBuilder,
+    table_xml::{TableWriter, validate_table_xml},
+    text_metrics::{LengthMetric, Redundancy, Script},
+    tokens::placeholders,
+};
+use indexmap::IndexMap;
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+
+// Entry id -> text of one XML file in one language
+pub type LanguageTable = IndexMap<EntryId, LastTextValue>;
+
+/// The tables of one XML file that are needed to combine one pair.
+#[derive(Debug, Clone, Default)]
+pub struct PairTables<'a> {
+    pub primary: Option<&'a LanguageTable>,
+    /// One table per extra language, in order; `None` when that language lacks the file.
+    pub secondaries: Vec<Option<&'a LanguageTable>>,
+    /// Tables of the fallback chain, in order.
+    pub fallbacks: Vec<&'a LanguageTable>,
+}
+
+/// What generating one pair did, for the generation summary.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PairReport {
+    pub output_dir: PathBuf,
+    /// `(file, entry id)` of every entry an override replaced.
+    pub overrides_applied: Vec<(String, String)>,
+    /// Overrides that matched no entry of the pair's files, as written in the override file.
+    pub overrides_unused: Vec<String>,
+    /// Where the decision of every entry was logged, if logging was on.
+    pub decision_log: Option<PathBuf>,
+    /// Combinations written primary only because the extra texts were redundant,
+    /// counted per `Redundancy` in the order of `Redundancy::ALL`.
+    pub suppressed: [usize; 3],
+    /// `(file, entry id)` of every entry whose extra texts have other placeholders than its
+    /// primary text, e.g. `%d` on one side only.
+    pub placeholder_mismatches: Vec<(String, String)>,
+}
+
+/// How the entries of a pair are decided.
+#[derive(Debug, Clone, Copy)]
+pub struct CombineSettings<'a> {
+    pub rule_set: &'a RuleSet,
+    pub overrides: &'a Overrides,
+    /// Write `decisions.csv` next to the pair's `Localization` folder.
+    pub decision_log: bool,
+}
+
+impl PairReport {
+    /// One summary line for the pair, followed by an indented line per override finding.
+    pub fn summary(&self, pair: &BilingualPair) -> String {
+        let mut summary = pair.describe();
+        if !self.overrides_applied.is_empty() {
+            let ids: Vec<&str> = self.overrides_applied.iter().map(|(_, id)| id.as_str()).collect();
+            summary.push_str(&format!("\n  {} override(s) applied: {}", ids.len(), ids.join(", ")));
+        }
+        if !self.overrides_unused.is_empty() {
+            summary.push_str(&format!("\n  Unused override(s), no such entry: {}", self.overrides_unused.join(", ")));
+        }
+        let suppressed: usize = self.suppressed.iter().sum();
+        if suppressed > 0 {
+            let counts: Vec<String> = Redundancy::ALL
+                .iter()
+                .filter(|redundancy| self.suppressed[**redundancy as usize] > 0)
+                .map(|redundancy| format!("{} {}", self.suppressed[*redundancy as usize], redundancy.name()))
+                .collect();
+            summary.push_str(&format!("\n  {} redundant combination(s) suppressed: {}", suppressed, counts.join(", ")));
+        }
+        if !self.placeholder_mismatches.is_empty() {
+            let ids: Vec<&str> = self.placeholder_mismatches.iter().map(|(_, id)| id.as_str()).collect();
+            summary.push_str(&format!(
+                "\n  Placeholders differ from the primary text in {} entry(ies): {}",
+                ids.len(),
+                ids.join(", ")
+            ));
+        }
+        if let Some(decision_log) = &self.decision_log {
+            summary.push_str(&format!("\n  Decision log: {}", decision_log.display()));
+        }
+        summary
+    }
+}
+
+/// Builds the output `<Table>` of one XML file, iterating the primary entries in order.
+/// A combination whose extra texts, fallback texts included, are all redundant (see
+/// `Redundancy`) is written primary only and counted in `report`; entries whose texts disagree on their placeholders are
+/// listed there too.
+/// Overrides replace the rule's text afterwards and are recorded in `report`; with logging on,
+/// the decision of every entry is added to `decisions`.
+/// The result is re-parsed and checked entry by entry before it is returned.
+pub fn build_table_xml(
+    file_name: &str,
+    primary: &LanguageTable,
+    tables: PairTables<'_>,
+    settings: CombineSettings<'_>,
+    report: &mut PairReport,
+    decisions: &mut Vec<Decision>,
+) -> Result<String, BilingualGeneratorError> {
+    let mut table = TableWriter::new(Vec::with_capacity(primary.len() * 128))?;
+    for (entry_id, primary_text) in primary {
+        let secondary_texts: Vec<Option<&str>> = tables
+            .secondaries
+            .iter()
+            .map(|table| table.and_then(|t| t.get(entry_id)).map(|lv| lv.0.as_str()))
+            .collect();
+        // First language of the chain that has a non-empty text for the entry
+        let fallback_text = tables
+            .fallbacks
+            .iter()
+            .filter_map(|t| t.get(entry_id))
+            .map(|lv| lv.0.as_str())
+            .find(|text| !text.is_empty());
+        if placeholders_differ(&primary_text.0, &secondary_texts) {
+            report.placeholder_mismatches.push((file_name.to_string(), entry_id.0.to_string()));
+        }
+        // What an entry written primary only shows
+        let primary_only = || settings.rule_set.normalization.primary_text(primary_text).0;
+        // Lengths are logged as the deciding rule measures them
+        let (decided_by, length_metric, combined_text) = if tables.secondaries.is_empty() {
+            ("language swap", LengthMetric::default(), primary_text.0.clone())
+        } else if let Some(entry_override) = settings.overrides.get(file_name, &entry_id.0) {
+            report.overrides_applied.push((file_name.to_string(), entry_id.0.to_string()));
+            let styled = settings
+                .rule_set
+                .styles
+                .apply(file_name, &extra_texts(&secondary_texts, fallback_text, usize::MAX));
+            let extra_texts: Vec<&str> = styled.iter().map(AsRef::as_ref).collect();
+            (
+                "override",
+                LengthMetric::default(),
+                entry_override.apply(
+                    primary_text,
+                    &extra_texts,
+                    &settings.rule_set.separators,
+                    &settings.rule_set.templates,
+                    settings.rule_set.order,
+                    &settings.rule_set.normalization,
+                ),
+            )
+        } else {
+            match settings.rule_set.resolve(file_name, entry_id, primary_text, &secondary_texts, fallback_text) {
+                Some((rule, text)) => {
+                    match redundancy(&primary_text.0, &rule.extra_texts(&secondary_texts, fallback_text)).filter(|_| text != primary_only()) {
+                        Some(redundancy) => {
+                            report.suppressed[redundancy as usize] += 1;
+                            (redundancy.name(), rule.length_metric, primary_only())
+                        }
+                        None => (rule.name.as_str(), rule.length_metric, text),
+                    }
+                }
+                None => ("no rule", LengthMetric::default(), primary_only()),
+            }
+        };
+        if settings.decision_log {
+            decisions.push(Decision {
+                file: file_name.to_string(),
+                entry_id: entry_id.0.to_string(),
+                primary_len: length_metric.measure(&primary_text.0),
+                secondary_len: secondary_texts.first().copied().flatten().map(|text| length_metric.measure(text)),
+                length_metric,
+                decided_by: decided_by.to_string(),
+                text: combined_text.to_string(),
+            });
+        }
+        table.write_row(entry_id, primary_text, &combined_text)?;
+    }
+    let (xml, written) = table.finish()?;
+    let xml = String::from_utf8(xml).map_err(|e| BilingualGeneratorError::XmlProcessingFailed(format!("Generated {} is not UTF-8: {}", file_name, e)))?;
+    validate_table_xml(file_name, &xml, &written)?;
+    Ok(xml)
+}
+
+// Why the extra texts shown, fallback texts included, add nothing next to the primary text
+// (the first text's reason), `None` if any of them does or none is present
+fn redundancy(primary_text: &str, extra_texts: &[&str]) -> Option<Redundancy> {
+    let present = extra_texts.iter().filter(|text| !text.is_empty() && **text != "MISSING");
+    let reasons: Option<Vec<Redundancy>> = present.map(|text| Redundancy::of(primary_text, text)).collect();
+    reasons?.first().copied()
+}
+
+// Whether an extra text present has other placeholders than the primary text
+fn placeholders_differ(primary_text: &str, secondary_texts: &[Option<&str>]) -> bool {
+    let primary = placeholders(primary_text);
+    secondary_texts
+        .iter()
+        .flatten()
+        .filter(|text| !text.is_empty() && **text != "MISSING")
+        .any(|text| placeholders(text) != primary)
+}
+
+/// Output directory of one pair: `<working_dir>/bilingual_xml/<output name>/Localization`.
+pub fn pair_output_dir(working_dir: &Path, pair: &BilingualPair) -> PathBuf {
+    working_dir.join("bilingual_xml").join(pair.output_name()).join("Localization")
+}
+
+/// The rule set a pair is combined with: its own `rule_set` if it names one, otherwise the
+/// built-in preset for the primary language's `script` unless the user provides the default
+/// rules; `rule_set = "default"` is the same as leaving it unset. The pair's separators,
+/// templates and styles are applied on top.
+pub fn pair_rule_set<'a>(
+    working_dir: &Path,
+    rule_set: &'a RuleSet,
+    pair: &BilingualPair,
+    script: Script,
+) -> Result<Cow<'a, RuleSet>, BilingualGeneratorError> {
+    let preset = preset_for_script(script);
+    let mut rule_set = match pair.rule_set.as_deref() {
+        Some(name) if name != "default" => Cow::Owned(RuleSet::load_named(working_dir, name)?),
+        _ if rule_set.preset.is_some_and(|name| name != preset) => Cow::Owned(RuleSet::load_named(working_dir, preset)?),
+        _ => Cow::Borrowed(rule_set),
+    };
+    if let Some(separators) = &pair.separators {
+        rule_set.to_mut().separators = separators.clone();
+    }
+    if let Some(templates) = &pair.templates {
+        rule_set.to_mut().templates = templates.clone();
+    }
+    if let Some(styles) = &pair.styles {
+        rule_set.to_mut().styles = styles.clone();
+    }
+    Ok(rule_set)
+}
+
+/// Combines every file of one pair and writes `{slot}_xml.pak`.
+///
+/// This is the single combine path used by both the rayon and the tokio orchestrators, so the
+/// same game files always produce the same pak. `tables_for` hands out the tables of one file.
+/// Tables go straight into an in-memory pak, so a failure never leaves XML files behind.
+/// A language swap (no secondary) writes the primary text unchanged, overrides included.
+/// `[[pair.file]]` options may pick another base language and text order per file; the output
+/// still goes into the one slot pak.
+/// Every other entry of the game's primary pak, files without table rows included, is copied
+/// over byte for byte, so the output replaces it completely.
+pub fn generate_pair_pak<'a>(
+    game_path: &Path,
+    working_dir: &Path,
+    files_to_process: &[String],
+    pair: &BilingualPair,
+    settings: CombineSettings<'_>,
+    tables_for: impl Fn(&XmlFile) -> PairTables<'a>,
+) -> Result<PairReport, BilingualGeneratorError> {
+    // Languages the game does not ship are told apart by the text of their first file
+    let script = Script::of_language(&pair.primary)
+        .or_else(|| {
+            let first_file = XmlFile(files_to_process.first()?.clone().into());
+            Script::detect(tables_for(&first_file).primary?.values().map(|text| text.0.as_str()))
+        })
+        .unwrap_or(Script::Cjk);
+    let rule_set = pair_rule_set(working_dir, settings.rule_set, pair, script)?;
+    let no_overrides = Overrides::default();
+    let settings = CombineSettings {
+        rule_set: &rule_set,
+        overrides: if pair.is_language_swap() { &no_overrides } else { settings.overrides },
+        ..settings
+    };
+    let file_filter = pair.file_filter()?;
+    let decision_log = pair_output_dir(working_dir, pair).with_file_name(DECISION_LOG_CSV);
+    // A log left by an earlier run would be explained as this run's output
+    if !settings.decision_log && decision_log.is_file() {
+        std::fs::remove_file(&decision_log)?;
+    }
+    let mut report = PairReport::default();
+    let mut decisions = Vec::new();
+    let mut pak = PakBuilder::new();
+    for file_name in files_to_process.iter().filter(|f| file_filter.matches(f)) {
+        let mut tables = tables_for(&XmlFile(file_name.clone().into()));
+        let Some(primary) = tables.primary else {
+            eprintln!(
+                "[Processor: {}] Warning: Missing primary data for file {}. Skipping file for this pair.",
+                pair, file_name
+            );
+            continue;
+        };
+        // Not a table (e.g. a font config) or no entries: the original is copied over unchanged
+        if primary.is_empty() {
+            continue;
+        }
+        if pair.is_language_swap() {
+            // Without secondary and fallback text every rule keeps the primary text
+            tables = PairTables {
+                primary: Some(primary),
+                ..Default::default()
+            };
+        }
+
+        let options = file_filter.file_options(file_name);
+        let mut based = None;
+        if let Some(base) = options.and_then(|options| options.base.as_deref())
+            && let Some(index) = pair.secondaries.iter().position(|language| language.as_str() == base)
+        {
+            match tables.secondaries[index] {
+                // The base language leads, the pair's primary takes its place among the extra
+                // languages. Entries stay those of the primary, one the base lacks keeps its text.
+                Some(base_table) => {
+                    tables.secondaries[index] = Some(primary);
+                    tables.primary = Some(base_table);
+                    based = Some(
+                        primary
+                            .iter()
+                            .map(|(entry_id, text)| (entry_id.clone(), base_table.get(entry_id).unwrap_or(text).clone()))
+                            .collect::<LanguageTable>(),
+                    );
+                }
+                None => eprintln!(
+                    "[Processor: {}] Warning: {} has no {}, keeping {} as its base.",
+                    pair, base, file_name, pair.primary
+                ),
+            }
+        }
+        let order = options.and_then(|options| options.order).unwrap_or(rule_set.order);
+        let file_rule_set = if order == rule_set.order {
+            Cow::Borrowed(&*rule_set)
+        } else {
+            Cow::Owned(RuleSet { order, ..(*rule_set).clone() })
+        };
+        let file_settings = CombineSettings {
+            rule_set: &file_rule_set,
+            ..settings
+        };
+
+        let primary = based.as_ref().unwrap_or(primary);
+        let xml_content = build_table_xml(file_name, primary, tables, file_settings, &mut report, &mut decisions)?;
+        pak.add_file(file_name, xml_content.as_bytes())?;
+    }
+
+    let original_pak = game_path.join("Localization").join(format!("{}_xml.pak", pair.primary));
+    let copied = pak.copy_remaining_from(&original_pak)?;
+    if copied > 0 {
+        println!("[Processor: {}] Copied {} untouched entries from {}.", pair, copied, original_pak.display());
+    }
+
+    for (scope, entry_id) in settings.overrides.keys() {
+        let applied = report
+            .overrides_applied
+            .iter()
+            .any(|(file, id)| id == entry_id && scope.is_none_or(|scope| scope == file));
+        if !applied {
+            report
+                .overrides_unused
+                .push(scope.map_or_else(|| entry_id.to_string(), |scope| format!("{}:{}", scope, entry_id)));
+        }
+    }
+
+    report.output_dir = pair_output_dir(working_dir, pair);
+    pak.finish(&report.output_dir, &pair.pak_name()).inspect_err(|e| {
+        eprintln!("[Processor: {}] Failed to create PAK: {:?}", pair, e);
+    })?;
+    if settings.decision_log {
+        write_decision_log(&decision_log, &decisions)?;
+        report.decision_log = Some(decision_log);
+    }
+    Ok(report)
+}
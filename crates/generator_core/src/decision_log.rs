@@ -0,0 +1,138 @@
+use super::{bilingual_generator_errors::BilingualGeneratorError, text_metrics::LengthMetric};
+use std::path::{Path, PathBuf};
+
+/// Name of the log written next to a pair's `Localization` folder.
+pub const DECISION_LOG_CSV: &str = "decisions.csv";
+
+static HEADER: [&str; 7] = ["file", "entry_id", "primary_len", "secondary_len", "length_metric", "decided_by", "text"];
+
+/// Why one entry got its output text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decision {
+    pub file: String,
+    pub entry_id: String,
+    /// Length of the primary text, measured with `length_metric`.
+    pub primary_len: usize,
+    /// Length of the first extra language's text, `None` when it is missing.
+    pub secondary_len: Option<usize>,
+    /// How the deciding rule measures lengths, the default metric when no rule decided.
+    pub length_metric: LengthMetric,
+    /// Name of the rule that fired, `override`, `no rule` or `language swap`.
+    pub decided_by: String,
+    pub text: String,
+}
+
+// Quotes a field when it holds a separator, a quote or a line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Splits CSV content into records, honouring quoted fields
+fn csv_records(content: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => record.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+/// Writes the decisions as CSV, one row per entry.
+pub fn write_decision_log(path: &Path, decisions: &[Decision]) -> Result<(), BilingualGeneratorError> {
+    let mut csv = HEADER.join(",");
+    csv.push('\n');
+    for decision in decisions {
+        let row = [
+            csv_field(&decision.file),
+            csv_field(&decision.entry_id),
+            decision.primary_len.to_string(),
+            decision.secondary_len.map_or(String::new(), |len| len.to_string()),
+            decision.length_metric.name().to_string(),
+            csv_field(&decision.decided_by),
+            csv_field(&decision.text),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    std::fs::write(path, csv)?;
+    Ok(())
+}
+
+/// Reads a log written by `write_decision_log`.
+pub fn read_decision_log(path: &Path) -> Result<Vec<Decision>, BilingualGeneratorError> {
+    let content = std::fs::read_to_string(path)?;
+    let invalid = |line: usize| BilingualGeneratorError::InvalidDecisionLog(format!("{} record {}", path.display(), line));
+    csv_records(&content)
+        .into_iter()
+        .enumerate()
+        .skip(1)
+        .map(|(index, record)| {
+            let [file, entry_id, primary_len, secondary_len, length_metric, decided_by, text] =
+                <[String; 7]>::try_from(record).map_err(|_| invalid(index + 1))?;
+            Ok(Decision {
+                file,
+                entry_id,
+                primary_len: primary_len.parse().map_err(|_| invalid(index + 1))?,
+                secondary_len: if secondary_len.is_empty() {
+                    None
+                } else {
+                    Some(secondary_len.parse().map_err(|_| invalid(index + 1))?)
+                },
+                length_metric: LengthMetric::from_name(&length_metric).ok_or_else(|| invalid(index + 1))?,
+                decided_by,
+                text,
+            })
+        })
+        .collect()
+}
+
+/// Every logged decision for `entry_id` (optionally only in `file`) across all pairs generated
+/// under `<working_dir>/bilingual_xml`, as `(pair output name, decision)` sorted by pair.
+pub fn explain_entry(working_dir: &Path, entry_id: &str, file: Option<&str>) -> Result<Vec<(String, Decision)>, BilingualGeneratorError> {
+    let output_root = working_dir.join("bilingual_xml");
+    let mut logs: Vec<(String, PathBuf)> = Vec::new();
+    if output_root.is_dir() {
+        for dir in std::fs::read_dir(&output_root)? {
+            let dir = dir?.path();
+            let log = dir.join(DECISION_LOG_CSV);
+            if log.is_file() {
+                logs.push((dir.file_name().unwrap_or_default().to_string_lossy().into_owned(), log));
+            }
+        }
+    }
+    logs.sort();
+
+    let mut found = Vec::new();
+    for (pair_name, log) in logs {
+        for decision in read_decision_log(&log)? {
+            if decision.entry_id == entry_id && file.is_none_or(|file| decision.file == file) {
+                found.push((pair_name.clone(), decision));
+            }
+        }
+    }
+    Ok(found)
+}
@@ -1,4 +1,16 @@
 pub mod async_read;
 pub mod bilingual_generator;
 pub mod bilingual_generator_errors;
+pub mod bilingual_set;
+pub mod calibration;
+pub mod combine_rules;
+pub mod combiner;
+pub mod decision_log;
+pub mod normalization;
+pub mod overrides;
+pub mod pak_builder;
+pub mod sentence_alignment;
+pub mod table_xml;
+pub mod text_metrics;
+pub mod tokens;
 pub mod util;
@@ -0,0 +1,107 @@
+use super::{
+    bilingual_generator::LastTextValue,
+    text_metrics::{Script, visible_chars},
+};
+use faststr::FastStr;
+use icu_normalizer::ComposingNormalizer;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+// Separator punctuation in its half-width and full-width form
+static WIDTH_FORMS: [(char, char); 5] = [('/', '／'), ('|', '｜'), (',', '，'), (';', '；'), (':', '：')];
+
+/// How texts and separators are tidied up. Every text a rule or an action override writes is
+/// normalized, a primary text written alone included; a language swap and a text override
+/// are written as they are. Set by a `[normalize]` table in a rule set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Normalization {
+    /// Compose every text to Unicode NFC, so "e\u{301}" is drawn as one "é".
+    pub nfc: bool,
+    /// Trim whitespace from both ends of every text and line breaks from its end.
+    pub trim: bool,
+    /// Adapt punctuation separators to the texts around them: full-width without spaces
+    /// between two CJK texts (`确定／好的`), half-width with spaces otherwise (`OK / 确定`).
+    pub separators: bool,
+}
+
+impl Default for Normalization {
+    fn default() -> Self {
+        Self {
+            nfc: true,
+            trim: true,
+            separators: false,
+        }
+    }
+}
+
+impl Normalization {
+    /// A text about to be joined, composed and trimmed as configured.
+    pub fn text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        if self.trim {
+            let mut trimmed = text.trim();
+            while let Some(rest) = trimmed.strip_suffix("\\n") {
+                trimmed = rest.trim_end();
+            }
+            if trimmed.len() != text.len() {
+                text = Cow::Owned(trimmed.to_string());
+            }
+        }
+        if self.nfc {
+            let normalizer = ComposingNormalizer::new_nfc();
+            if !normalizer.is_normalized(&text) {
+                text = Cow::Owned(normalizer.normalize(&text));
+            }
+        }
+        text
+    }
+
+    /// The primary text tidied up by `text`, for an entry written primary only.
+    pub fn primary_text(&self, primary_text: &LastTextValue) -> LastTextValue {
+        match self.text(&primary_text.0) {
+            Cow::Borrowed(_) => primary_text.clone(),
+            Cow::Owned(text) => LastTextValue(FastStr::new(text)),
+        }
+    }
+
+    /// The primary text and the extra texts that are shown (neither `MISSING` nor empty),
+    /// each tidied up by `text`.
+    pub fn texts<'a>(&self, primary_text: &LastTextValue, extra_texts: &[&'a str]) -> (LastTextValue, Vec<Cow<'a, str>>) {
+        let primary_text = self.primary_text(primary_text);
+        let extra_texts = extra_texts
+            .iter()
+            .map(|text| self.text(text))
+            .filter(|text| text != "MISSING" && !text.is_empty())
+            .collect();
+        (primary_text, extra_texts)
+    }
+
+    /// The separator to put between `before` and `after`. Only a separator made of `/ | , ; :`
+    /// (half-width or full-width, spaces around) is adapted: full-width when both neighbouring
+    /// texts are CJK, half-width otherwise, `/` and `|` with a space on both sides and the
+    /// others with one after. Line breaks and any other separator stay as they are.
+    pub fn separator<'a>(&self, separator: &'a str, before: &str, after: &str) -> Cow<'a, str> {
+        let core = separator.trim_matches([' ', '\u{3000}']);
+        let adaptable = !core.is_empty() && core.chars().all(|c| WIDTH_FORMS.iter().any(|(half, full)| c == *half || c == *full));
+        if !self.separators || !adaptable {
+            return Cow::Borrowed(separator);
+        }
+        let full_width = visible_chars(before).filter_map(Script::of_char).last() == Some(Script::Cjk)
+            && visible_chars(after).find_map(Script::of_char) == Some(Script::Cjk);
+        let core: String = core
+            .chars()
+            .map(|c| {
+                let (half, full) = WIDTH_FORMS.iter().find(|(half, full)| c == *half || c == *full).copied().unwrap_or((c, c));
+                if full_width { full } else { half }
+            })
+            .collect();
+        Cow::Owned(if full_width {
+            core
+        } else if core.chars().all(|c| c == '/' || c == '|') {
+            format!(" {} ", core)
+        } else {
+            format!("{} ", core)
+        })
+    }
+}
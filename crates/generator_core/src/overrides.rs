@@ -0,0 +1,186 @@
+use super::{
+    bilingual_generator::LastTextValue,
+    bilingual_generator_errors::BilingualGeneratorError,
+    combine_rules::{Separators, Templates, TextOrder, render_template},
+    normalization::Normalization,
+    util::texts_combined_secondary_first,
+};
+use faststr::FastStr;
+use serde::Deserialize;
+use std::{collections::BTreeMap, path::Path};
+
+pub const OVERRIDES_TOML: &str = "overrides.toml";
+
+/// Forced output of an entry, whatever the rules decided.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverrideAction {
+    PrimaryOnly,
+    Slash,
+    Newline,
+    /// The extra languages first, then the primary text, one per line.
+    SecondaryFirst,
+}
+
+/// What an override does to one entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryOverride {
+    Action(OverrideAction),
+    /// Literal replacement text.
+    Text(String),
+}
+
+// `id = "slash"`, `id = { action = "slash" }` or `id = { text = "..." }`
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum OverrideConfig {
+    Action(OverrideAction),
+    Table(OverrideTable),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OverrideTable {
+    action: Option<OverrideAction>,
+    text: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OverridesFile {
+    #[serde(default)]
+    ids: BTreeMap<String, OverrideConfig>,
+    #[serde(default)]
+    files: BTreeMap<String, BTreeMap<String, OverrideConfig>>,
+}
+
+/// User overrides by entry id, applied after the combine rules. An override scoped to a
+/// file takes precedence over one for the same id in every file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Overrides {
+    ids: BTreeMap<String, EntryOverride>,
+    files: BTreeMap<String, BTreeMap<String, EntryOverride>>,
+}
+
+impl TryFrom<OverrideConfig> for EntryOverride {
+    type Error = String;
+
+    fn try_from(config: OverrideConfig) -> Result<Self, Self::Error> {
+        match config {
+            OverrideConfig::Action(action)
+            | OverrideConfig::Table(OverrideTable {
+                action: Some(action),
+                text: None,
+            }) => Ok(EntryOverride::Action(action)),
+            OverrideConfig::Table(OverrideTable {
+                action: None,
+                text: Some(text),
+            }) => Ok(EntryOverride::Text(text)),
+            OverrideConfig::Table(_) => Err("needs either an action or a text".to_string()),
+        }
+    }
+}
+
+impl EntryOverride {
+    /// Builds the output text. `extra_texts` are the texts of the extra languages, in order.
+    /// `slash` and `newline` use the pair's templates when it has them, else its text order;
+    /// `secondary_first` uses the newline template or joins the texts like `TextOrder::SecondaryFirst`.
+    /// Texts taken from the game are tidied up by `normalization`, the text of a text override is not.
+    pub fn apply(
+        &self,
+        primary_text: &LastTextValue,
+        extra_texts: &[&str],
+        separators: &Separators,
+        templates: &Templates,
+        order: TextOrder,
+        normalization: &Normalization,
+    ) -> FastStr {
+        let action = match self {
+            EntryOverride::Text(text) => return FastStr::new(text),
+            EntryOverride::Action(OverrideAction::PrimaryOnly) => return normalization.primary_text(primary_text).0,
+            EntryOverride::Action(action) => *action,
+        };
+        let template = match action {
+            OverrideAction::Slash => templates.slash.as_deref(),
+            OverrideAction::Newline | OverrideAction::SecondaryFirst => templates.newline.as_deref(),
+            OverrideAction::PrimaryOnly => None,
+        };
+        let (normalized_primary, normalized) = normalization.texts(primary_text, extra_texts);
+        let shown: Vec<&str> = normalized.iter().map(AsRef::as_ref).collect();
+        if shown.is_empty() {
+            return normalized_primary.0;
+        }
+        let primary_text = &normalized_primary;
+        if let Some(template) = template {
+            return render_template(template, &primary_text.0, &shown).into();
+        }
+        match action {
+            OverrideAction::PrimaryOnly => primary_text.0.clone(),
+            OverrideAction::Slash => order.combine(primary_text, shown.iter().map(|text| (separators.slash.as_str(), *text)), normalization),
+            OverrideAction::Newline => order.combine(primary_text, shown.iter().map(|text| (separators.newline.as_str(), *text)), normalization),
+            OverrideAction::SecondaryFirst => {
+                texts_combined_secondary_first(primary_text, shown.iter().map(|text| (separators.newline.as_str(), *text)), normalization)
+            }
+        }
+    }
+}
+
+impl Overrides {
+    pub fn from_toml_str(content: &str) -> Result<Self, BilingualGeneratorError> {
+        let invalid = |message: String| BilingualGeneratorError::InvalidOverrides(message);
+        let file: OverridesFile = toml::from_str(content).map_err(|e| invalid(e.to_string()))?;
+        let convert = |entries: BTreeMap<String, OverrideConfig>, scope: &str| {
+            entries
+                .into_iter()
+                .map(|(id, config)| {
+                    let entry_override = EntryOverride::try_from(config).map_err(|e| invalid(format!("{}'{}' {}", scope, id, e)))?;
+                    Ok((id, entry_override))
+                })
+                .collect::<Result<BTreeMap<_, _>, BilingualGeneratorError>>()
+        };
+        let mut files = BTreeMap::new();
+        for (file_name, entries) in file.files {
+            let scope = format!("{}: ", file_name);
+            files.insert(file_name, convert(entries, &scope)?);
+        }
+        Ok(Self {
+            ids: convert(file.ids, "")?,
+            files,
+        })
+    }
+
+    /// Loads `assets/overrides.toml` from the working directory; no file means no overrides.
+    pub fn load(working_dir: &Path) -> Result<Self, BilingualGeneratorError> {
+        let path = working_dir.join("assets").join(OVERRIDES_TOML);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        Self::from_toml_str(&content).map_err(|e| match e {
+            BilingualGeneratorError::InvalidOverrides(message) => BilingualGeneratorError::InvalidOverrides(format!("{}: {}", path.display(), message)),
+            other => other,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty() && self.files.values().all(BTreeMap::is_empty)
+    }
+
+    /// The override for an entry of a file, if any.
+    pub fn get(&self, file_name: &str, entry_id: &str) -> Option<&EntryOverride> {
+        self.files
+            .get(file_name)
+            .and_then(|entries| entries.get(entry_id))
+            .or_else(|| self.ids.get(entry_id))
+    }
+
+    /// Every override as `(file scope, entry id)`, a `None` scope applies in every file.
+    pub fn keys(&self) -> impl Iterator<Item = (Option<&str>, &str)> {
+        let ids = self.ids.keys().map(|id| (None, id.as_str()));
+        let files = self
+            .files
+            .iter()
+            .flat_map(|(file, entries)| entries.keys().map(move |id| (Some(file.as_str()), id.as_str())));
+        ids.chain(files)
+    }
+}
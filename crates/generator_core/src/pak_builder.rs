@@ -0,0 +1,91 @@
+use super::bilingual_generator_errors::BilingualGeneratorError;
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{Cursor, Write},
+    path::{Path, PathBuf},
+};
+use zip::{
+    CompressionMethod, DateTime, ZipArchive, ZipWriter,
+    write::{ExtendedFileOptions, FileOptions},
+};
+
+/// Builds a pak in memory; nothing touches the disk until `finish`.
+///
+/// If generation fails or the builder is dropped early, no partial pak or XML file is left behind.
+pub struct PakBuilder {
+    zip: ZipWriter<Cursor<Vec<u8>>>,
+    options: FileOptions<'static, ExtendedFileOptions>,
+    // Entries added so far, never copied again from the original pak
+    names: HashSet<String>,
+}
+
+impl PakBuilder {
+    pub fn new() -> Self {
+        Self {
+            zip: ZipWriter::new(Cursor::new(Vec::new())),
+            // Fixed timestamp so the same input always produces a byte-identical pak
+            options: FileOptions::default()
+                .compression_method(CompressionMethod::Deflated)
+                .last_modified_time(DateTime::default()),
+            names: HashSet::new(),
+        }
+    }
+
+    /// Adds one file (e.g. a generated `<Table>`) as a pak entry.
+    pub fn add_file(&mut self, name: &str, content: &[u8]) -> Result<(), BilingualGeneratorError> {
+        self.zip
+            .start_file(name, self.options.clone())
+            .map_err(|_| BilingualGeneratorError::PakCreationFailed)?;
+        self.zip.write_all(content).map_err(|_| BilingualGeneratorError::PakCreationFailed)?;
+        self.names.insert(name.to_string());
+        Ok(())
+    }
+
+    /// Copies every entry of `original_pak` that was not added yet, so the output is a superset
+    /// of the pak it replaces. Entries are copied still compressed, byte-for-byte.
+    /// Returns the number of copied entries.
+    pub fn copy_remaining_from(&mut self, original_pak: &Path) -> Result<usize, BilingualGeneratorError> {
+        let pak_error = |operation: &str, source: std::io::Error| BilingualGeneratorError::PakOperationFailed {
+            operation: operation.to_string(),
+            context: original_pak.display().to_string(),
+            source,
+        };
+        let file = File::open(original_pak).map_err(|e| pak_error("opening original PAK", e))?;
+        let mut archive = ZipArchive::new(file).map_err(|e| pak_error("creating ZipArchive from original PAK", e.into()))?;
+        let mut copied = 0;
+        for index in 0..archive.len() {
+            let entry = archive.by_index_raw(index).map_err(|e| pak_error("reading original PAK entry", e.into()))?;
+            if self.names.contains(entry.name()) {
+                continue;
+            }
+            let name = entry.name().to_string();
+            self.zip.raw_copy_file(entry).map_err(|_| BilingualGeneratorError::PakCreationFailed)?;
+            self.names.insert(name);
+            copied += 1;
+        }
+        Ok(copied)
+    }
+
+    /// Writes the pak to `output_dir/pak_name` and returns its path.
+    /// The pak is written to a temporary name first and renamed once complete.
+    pub fn finish(self, output_dir: &Path, pak_name: &str) -> Result<PathBuf, BilingualGeneratorError> {
+        let bytes = self.zip.finish().map_err(|_| BilingualGeneratorError::PakCreationFailed)?.into_inner();
+        std::fs::create_dir_all(output_dir)?;
+        let pak_path = output_dir.join(pak_name);
+        let partial_path = output_dir.join(format!("{}.partial", pak_name));
+        std::fs::write(&partial_path, bytes).inspect_err(|_| {
+            let _ = std::fs::remove_file(&partial_path);
+        })?;
+        std::fs::rename(&partial_path, &pak_path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&partial_path);
+        })?;
+        Ok(pak_path)
+    }
+}
+
+impl Default for PakBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,83 @@
+use super::text_metrics::char_count;
+
+// Punctuation ending a sentence wherever it stands
+static CJK_TERMINATORS: [char; 3] = ['。', '！', '？'];
+// Punctuation ending a sentence only before a space or the end of the text
+static LATIN_TERMINATORS: [char; 4] = ['.', '!', '?', '…'];
+// Closing quotes and brackets that stay with the sentence they end
+static CLOSERS: [char; 8] = ['"', '\'', '”', '’', '」', '』', ')', '）'];
+
+/// Largest difference between a sentence's share of the primary text and its counterpart's
+/// share of the secondary text, beyond which the alignment is not trusted.
+pub const MAX_SHARE_DIFFERENCE: f64 = 0.2;
+
+/// Splits a text into trimmed sentences. CJK `。！？` always end a sentence, Latin `.!?…`
+/// only before whitespace, so "3.5" stays whole; closing quotes stay with their sentence.
+/// A line break, literal `\n` included, ends a sentence as well and is dropped.
+pub fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let line_break_len = match c {
+            '\n' => 1,
+            '\\' if text[index + 1..].starts_with('n') => 2,
+            _ => 0,
+        };
+        if line_break_len > 0 {
+            sentences.push(&text[start..index]);
+            if line_break_len == 2 {
+                chars.next();
+            }
+            start = index + line_break_len;
+            continue;
+        }
+        let cjk = CJK_TERMINATORS.contains(&c);
+        if !cjk && !LATIN_TERMINATORS.contains(&c) {
+            continue;
+        }
+        let mut end = index + c.len_utf8();
+        while let Some(&(next_index, next)) = chars.peek() {
+            if !(CJK_TERMINATORS.contains(&next) || LATIN_TERMINATORS.contains(&next) || CLOSERS.contains(&next)) {
+                break;
+            }
+            end = next_index + next.len_utf8();
+            chars.next();
+        }
+        if cjk || chars.peek().is_none_or(|(_, next)| next.is_whitespace()) {
+            sentences.push(&text[start..end]);
+            start = end;
+        }
+    }
+    sentences.push(&text[start..]);
+    sentences.into_iter().map(str::trim).filter(|sentence| !sentence.is_empty()).collect()
+}
+
+/// The first sentence of `text` ending in `…` instead of its terminator (`.!?` or `。！？`),
+/// `None` when the text is a single sentence.
+pub fn first_sentence(text: &str) -> Option<String> {
+    let sentences = split_sentences(text);
+    let (first, [_, ..]) = sentences.split_first()? else {
+        return None;
+    };
+    let first = first.trim_end_matches(|c| CJK_TERMINATORS.contains(&c) || LATIN_TERMINATORS.contains(&c));
+    Some(format!("{}…", first))
+}
+
+/// Pairs the sentences of both texts in order. `None` when the alignment is unreliable: fewer
+/// than two sentences, different sentence counts, or a pair whose shares of their texts differ
+/// by more than `MAX_SHARE_DIFFERENCE`.
+pub fn align_sentences<'a>(primary_text: &'a str, secondary_text: &'a str) -> Option<Vec<(&'a str, &'a str)>> {
+    let primary = split_sentences(primary_text);
+    let secondary = split_sentences(secondary_text);
+    if primary.len() < 2 || primary.len() != secondary.len() {
+        return None;
+    }
+    let total = |sentences: &[&str]| sentences.iter().map(|sentence| char_count(sentence)).sum::<usize>().max(1) as f64;
+    let (primary_total, secondary_total) = (total(&primary), total(&secondary));
+    let similar = primary
+        .iter()
+        .zip(&secondary)
+        .all(|(p, s)| (char_count(p) as f64 / primary_total - char_count(s) as f64 / secondary_total).abs() <= MAX_SHARE_DIFFERENCE);
+    similar.then(|| primary.into_iter().zip(secondary).collect())
+}
@@ -0,0 +1,69 @@
+use super::{
+    bilingual_generator::{EntryId, LastTextValue},
+    bilingual_generator_errors::BilingualGeneratorError,
+    util::parse_table_xml,
+};
+use faststr::FastStr;
+use quick_xml::{
+    Writer,
+    escape::partial_escape,
+    events::{BytesEnd, BytesStart, BytesText, Event},
+};
+use std::io::Write;
+
+/// Writes a localization `<Table>`; every cell is XML-escaped.
+pub struct TableWriter<W: Write> {
+    writer: Writer<W>,
+    // Entry id and combined text of every row, checked by `validate`
+    written: Vec<(EntryId, FastStr)>,
+}
+
+impl<W: Write> TableWriter<W> {
+    pub fn new(inner: W) -> Result<Self, BilingualGeneratorError> {
+        let mut writer = Writer::new(inner);
+        writer.write_event(Event::Start(BytesStart::new("Table")))?;
+        writer.get_mut().write_all(b"\n")?;
+        Ok(Self { writer, written: Vec::new() })
+    }
+
+    pub fn write_row(&mut self, entry_id: &EntryId, primary_text: &LastTextValue, combined_text: &FastStr) -> Result<(), BilingualGeneratorError> {
+        self.writer.write_event(Event::Start(BytesStart::new("Row")))?;
+        for cell in [entry_id.0.as_str(), primary_text.0.as_str(), combined_text.as_str()] {
+            self.writer.write_event(Event::Start(BytesStart::new("Cell")))?;
+            // Only `<`, `>` and `&` need escaping inside element text
+            self.writer.write_event(Event::Text(BytesText::from_escaped(partial_escape(cell))))?;
+            self.writer.write_event(Event::End(BytesEnd::new("Cell")))?;
+        }
+        self.writer.write_event(Event::End(BytesEnd::new("Row")))?;
+        self.writer.get_mut().write_all(b"\n")?;
+        self.written.push((entry_id.clone(), combined_text.clone()));
+        Ok(())
+    }
+
+    /// Closes the table and returns the writer together with the rows that were written.
+    pub fn finish(mut self) -> Result<(W, Vec<(EntryId, FastStr)>), BilingualGeneratorError> {
+        self.writer.write_event(Event::End(BytesEnd::new("Table")))?;
+        Ok((self.writer.into_inner(), self.written))
+    }
+}
+
+/// Re-parses a generated table and checks that every entry id round-trips to the expected text.
+pub fn validate_table_xml(file_name: &str, xml: &str, expected: &[(EntryId, FastStr)]) -> Result<(), BilingualGeneratorError> {
+    let invalid = |entry_id: &str, reason: String| BilingualGeneratorError::XmlValidationFailed {
+        file: file_name.to_string(),
+        entry_id: entry_id.to_string(),
+        reason,
+    };
+    let parsed = parse_table_xml(xml, file_name).map_err(|e| invalid("-", e.to_string()))?;
+    if parsed.len() != expected.len() {
+        return Err(invalid("-", format!("expected {} rows, found {}", expected.len(), parsed.len())));
+    }
+    for (entry_id, text) in expected {
+        match parsed.get(entry_id) {
+            Some(parsed_text) if parsed_text.0 == *text => {}
+            Some(parsed_text) => return Err(invalid(&entry_id.0, format!("text round-trips as {:?}, expected {:?}", parsed_text.0, text))),
+            None => return Err(invalid(&entry_id.0, "entry is missing after re-parsing".to_string())),
+        }
+    }
+    Ok(())
+}
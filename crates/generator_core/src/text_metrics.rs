@@ -0,0 +1,263 @@
+use super::{
+    bilingual_generator_errors::BilingualGeneratorError,
+    tokens::{Token, tokenize},
+};
+use ab_glyph::{Font, FontArc};
+use icu_normalizer::DecomposingNormalizer;
+use serde::Deserialize;
+use std::path::Path;
+use unicode_width::UnicodeWidthChar;
+
+/// How rule length conditions measure a text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LengthMetric {
+    /// Every visible character counts 1.
+    #[default]
+    Chars,
+    /// Display columns: East Asian wide and fullwidth characters count 2, combining marks 0.
+    Width,
+}
+
+impl LengthMetric {
+    /// Name used in rule files, `chars` or `width`.
+    pub fn name(self) -> &'static str {
+        match self {
+            LengthMetric::Chars => "chars",
+            LengthMetric::Width => "width",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [LengthMetric::Chars, LengthMetric::Width].into_iter().find(|metric| metric.name() == name)
+    }
+
+    pub fn measure(self, text: &str) -> usize {
+        match self {
+            LengthMetric::Chars => char_count(text),
+            LengthMetric::Width => display_width(text),
+        }
+    }
+}
+
+/// Characters of `text` as they are shown in game.
+/// Escape sequences such as the literal `\n` separator, markup tags such as
+/// `<font color="#a0a0a0">` and placeholders such as `%d` are skipped, they are not drawn
+/// as written.
+pub fn visible_chars(text: &str) -> impl Iterator<Item = char> + '_ {
+    visible_char_indices(text).map(|(_, c)| c)
+}
+
+/// `visible_chars` together with their byte offsets in `text`.
+pub fn visible_char_indices(text: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    tokenize(text)
+        .into_iter()
+        .filter_map(|(start, token)| match token {
+            Token::Text(text) => Some(text.char_indices().map(move |(index, c)| (start + index, c))),
+            _ => None,
+        })
+        .flatten()
+        .filter(|(_, c)| !c.is_control())
+}
+
+/// Number of visible characters.
+pub fn char_count(text: &str) -> usize {
+    visible_chars(text).count()
+}
+
+/// Number of display columns, a CJK character is twice as wide as a Latin letter.
+pub fn display_width(text: &str) -> usize {
+    visible_chars(text).map(|c| c.width().unwrap_or(0)).sum()
+}
+
+/// The start of `text` that fits in `columns` display columns followed by `…`, `None` when
+/// the whole text fits. Text with spaces is cut after the last whole word, other text
+/// (such as CJK) after the last whole character. Markup tags still open at the cut are closed
+/// after the `…`, so the game text stays balanced.
+pub fn truncate_to_width(text: &str, columns: usize) -> Option<String> {
+    if display_width(text) <= columns {
+        return None;
+    }
+    // The ellipsis takes one column
+    let budget = columns.saturating_sub(1);
+    let (mut width, mut cut, mut word_end) = (0, 0, None);
+    for (index, c) in visible_char_indices(text) {
+        let char_width = c.width().unwrap_or(0);
+        if width + char_width > budget {
+            if !c.is_whitespace() {
+                cut = word_end.unwrap_or(cut);
+            }
+            break;
+        }
+        if c.is_whitespace() {
+            word_end = Some(index);
+        }
+        width += char_width;
+        cut = index + c.len_utf8();
+    }
+    let kept = text[..cut].trim_end();
+    let mut open: Vec<&str> = Vec::new();
+    for (_, token) in tokenize(kept) {
+        let Token::Tag(tag) = token else {
+            continue;
+        };
+        let name = tag
+            .trim_start_matches(['<', '/'])
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .next()
+            .unwrap_or("");
+        if tag.starts_with("</") {
+            if let Some(position) = open.iter().rposition(|open_name| *open_name == name) {
+                open.truncate(position);
+            }
+        } else if !tag.ends_with("/>") {
+            open.push(name);
+        }
+    }
+    let closing: String = open.iter().rev().map(|name| format!("</{}>", name)).collect();
+    Some(format!("{}…{}", kept, closing))
+}
+
+/// A TTF/OTF font, measures how wide a text is rendered in game.
+#[derive(Debug, Clone)]
+pub struct FontMetrics {
+    font: FontArc,
+    units_per_em: f32,
+}
+
+impl FontMetrics {
+    pub fn load(path: &Path) -> Result<Self, BilingualGeneratorError> {
+        let invalid = |message: String| BilingualGeneratorError::InvalidRuleSet(format!("font {}: {}", path.display(), message));
+        let data = std::fs::read(path).map_err(|e| invalid(e.to_string()))?;
+        let font = FontArc::try_from_vec(data).map_err(|e| invalid(e.to_string()))?;
+        let units_per_em = font.units_per_em().ok_or_else(|| invalid("no units per em".to_string()))?;
+        Ok(Self { font, units_per_em })
+    }
+
+    /// Width in pixels of one line at `size` pixels per em, kerning included.
+    pub fn line_width(&self, line: &str, size: f32) -> f32 {
+        let mut units = 0.0;
+        let mut previous = None;
+        for c in visible_chars(line) {
+            let glyph = self.font.glyph_id(c);
+            if let Some(previous) = previous {
+                units += self.font.kern_unscaled(previous, glyph);
+            }
+            units += self.font.h_advance_unscaled(glyph);
+            previous = Some(glyph);
+        }
+        units * size / self.units_per_em
+    }
+}
+
+/// Rendered width in pixels of the widest line of `text` at `size` pixels per em.
+/// Without a font every display column is taken as half an em wide.
+pub fn rendered_width(text: &str, size: f32, font: Option<&FontMetrics>) -> f32 {
+    text.split("\\n")
+        .flat_map(|line| line.split('\n'))
+        .map(|line| match font {
+            Some(font) => font.line_width(line, size),
+            None => display_width(line) as f32 * size / 2.0,
+        })
+        .fold(0.0, f32::max)
+}
+
+/// Writing system of a language, picks the built-in rule preset for a primary language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Script {
+    /// Chinese, Japanese, Korean: dense, every character is a full em wide.
+    Cjk,
+    Latin,
+    Cyrillic,
+}
+
+impl Script {
+    /// Script of a game localization language, `None` for a language the game does not ship.
+    pub fn of_language(language: &str) -> Option<Script> {
+        match language {
+            "Chineses" | "Chineset" | "Japanese" | "Korean" => Some(Script::Cjk),
+            "Russian" | "Ukrainian" => Some(Script::Cyrillic),
+            "English" | "German" | "French" | "Czech" | "Polish" | "Italian" | "Spanish" | "Portuguese" | "Turkish" => Some(Script::Latin),
+            _ => None,
+        }
+    }
+
+    /// Script of a single character, `None` for digits, punctuation and the like.
+    pub fn of_char(c: char) -> Option<Script> {
+        match c {
+            '\u{1100}'..='\u{11FF}' | '\u{3040}'..='\u{30FF}' | '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{AC00}'..='\u{D7AF}' => {
+                Some(Script::Cjk)
+            }
+            '\u{0400}'..='\u{052F}' => Some(Script::Cyrillic),
+            c if c.is_alphabetic() && c <= '\u{024F}' => Some(Script::Latin),
+            _ => None,
+        }
+    }
+
+    /// The script most letters of `texts` are written in.
+    pub fn detect<'a>(texts: impl IntoIterator<Item = &'a str>) -> Option<Script> {
+        let mut counts = [0usize; 3];
+        for c in texts.into_iter().flat_map(visible_chars) {
+            if let Some(script) = Script::of_char(c) {
+                counts[script as usize] += 1;
+            }
+        }
+        [Script::Cjk, Script::Latin, Script::Cyrillic]
+            .into_iter()
+            .filter(|script| counts[*script as usize] > 0)
+            .max_by_key(|script| counts[*script as usize])
+    }
+}
+
+/// Why an extra text adds nothing next to the primary text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Redundancy {
+    /// Exactly the primary text, e.g. a name.
+    Identical,
+    /// The primary text up to case, whitespace, diacritics and markup.
+    NearIdentical,
+    /// Only numbers, punctuation and placeholders.
+    NoWords,
+}
+
+impl Redundancy {
+    pub const ALL: [Redundancy; 3] = [Redundancy::Identical, Redundancy::NearIdentical, Redundancy::NoWords];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Redundancy::Identical => "identical",
+            Redundancy::NearIdentical => "near identical",
+            Redundancy::NoWords => "no words",
+        }
+    }
+
+    /// Why `secondary_text` is not worth showing after `primary_text`, `None` if it is.
+    pub fn of(primary_text: &str, secondary_text: &str) -> Option<Redundancy> {
+        if primary_text == secondary_text {
+            Some(Redundancy::Identical)
+        } else if comparison_key(primary_text) == comparison_key(secondary_text) {
+            Some(Redundancy::NearIdentical)
+        } else if !has_words(secondary_text) {
+            Some(Redundancy::NoWords)
+        } else {
+            None
+        }
+    }
+}
+
+/// The visible text lowercased, without whitespace and diacritics: "Éte\u{301} " and "ete" match.
+pub fn comparison_key(text: &str) -> String {
+    let visible: String = visible_chars(text).filter(|c| !c.is_whitespace()).collect();
+    // Canonical decomposition moves diacritics into combining marks, which take no column
+    DecomposingNormalizer::new_nfd()
+        .normalize(&visible)
+        .chars()
+        .filter(|c| c.width() != Some(0))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Whether `text` has a letter outside of its placeholders and markup.
+pub fn has_words(text: &str) -> bool {
+    visible_chars(text).any(char::is_alphabetic)
+}
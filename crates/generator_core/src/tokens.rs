@@ -0,0 +1,93 @@
+use regex::Regex;
+use std::{borrow::Cow, collections::BTreeSet, sync::LazyLock};
+
+// printf style `%d`, `%1$s`, `%%`, braced `{0}` and dollar `$NAME$` placeholders at the start
+// of the rest of a text. No space flag for printf, so "100% damage" keeps its "d".
+static PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:%(\d+\$)?[-+0#]*\d*(\.\d+)?[a-zA-Z%]|\{[^{}\s]*\}|\$[^$\s]+\$)").expect("valid regex"));
+
+/// A piece of a game string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token<'a> {
+    /// Text drawn as it is.
+    Text(&'a str),
+    /// Escape sequence such as the literal `\n` line break.
+    Escape(&'a str),
+    /// Markup tag such as `<font color="#a0a0a0">` or `</font>`.
+    Tag(&'a str),
+    /// Value the game fills in: printf style `%d` or `%1$s`, braced `{0}`, or `$NAME$`.
+    Placeholder(&'a str),
+}
+
+impl<'a> Token<'a> {
+    pub fn as_str(self) -> &'a str {
+        match self {
+            Token::Text(text) | Token::Escape(text) | Token::Tag(text) | Token::Placeholder(text) => text,
+        }
+    }
+}
+
+/// Splits `text` into tokens, each with its byte offset; together they make up `text`.
+/// A `<` that does not start a tag and an unmatched `{` or `%` are plain text.
+pub fn tokenize(text: &str) -> Vec<(usize, Token<'_>)> {
+    let mut tokens = Vec::new();
+    let (mut text_start, mut index) = (0, 0);
+    while let Some(c) = text[index..].chars().next() {
+        let rest = &text[index..];
+        let token = match c {
+            '\\' => Some(Token::Escape(&rest[..1 + rest[1..].chars().next().map_or(0, char::len_utf8)])),
+            '<' => markup_tag_len(&rest[1..]).map(|len| Token::Tag(&rest[..1 + len])),
+            '%' | '{' | '$' => PLACEHOLDER.find(rest).map(|m| Token::Placeholder(m.as_str())),
+            _ => None,
+        };
+        let Some(token) = token else {
+            index += c.len_utf8();
+            continue;
+        };
+        if text_start < index {
+            tokens.push((text_start, Token::Text(&text[text_start..index])));
+        }
+        tokens.push((index, token));
+        index += token.as_str().len();
+        text_start = index;
+    }
+    if text_start < text.len() {
+        tokens.push((text_start, Token::Text(&text[text_start..])));
+    }
+    tokens
+}
+
+// Length of the rest of a markup tag whose `<` was just read, `None` when `<` is plain text
+fn markup_tag_len(rest: &str) -> Option<usize> {
+    let name = rest.strip_prefix('/').unwrap_or(rest);
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let end = rest.find(['<', '>'])?;
+    (rest.as_bytes()[end] == b'>').then_some(end + 1)
+}
+
+/// The distinct placeholders of `text`.
+pub fn placeholders(text: &str) -> BTreeSet<&str> {
+    tokenize(text)
+        .into_iter()
+        .filter_map(|(_, token)| match token {
+            Token::Placeholder(placeholder) => Some(placeholder),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `text` without its markup tags; escapes and placeholders stay.
+pub fn strip_markup(text: &str) -> Cow<'_, str> {
+    let tokens = tokenize(text);
+    if !tokens.iter().any(|(_, token)| matches!(token, Token::Tag(_))) {
+        return Cow::Borrowed(text);
+    }
+    tokens
+        .into_iter()
+        .filter(|(_, token)| !matches!(token, Token::Tag(_)))
+        .map(|(_, token)| token.as_str())
+        .collect::<String>()
+        .into()
+}
@@ -1,47 +1,208 @@
-use super::{bilingual_generator::LastTextValue, bilingual_generator_errors::BilingualGeneratorError};
+use super::{
+    bilingual_generator::{EntryId, LastTextValue, XmlFile},
+    bilingual_generator_errors::BilingualGeneratorError,
+    normalization::Normalization,
+    pak_builder::PakBuilder,
+};
 use faststr::FastStr;
+use indexmap::IndexMap;
+use quick_xml::{Reader, events::Event};
 use std::{
+    collections::HashMap,
     fs::File,
-    io::Write,
+    io::Read,
     path::{Path, PathBuf},
 };
-use zip::{
-    CompressionMethod, ZipWriter,
-    write::{ExtendedFileOptions, FileOptions},
-};
+use zip::ZipArchive;
 // Define separators
 // Use actual newline '\n' if the target system/game expects that.
 // Use escaped "\\n" if the target system expects the literal characters '\' and 'n'.
 pub static SEPARATOR_SLASH: &str = "/";
 pub static SEPARATOR_NEWLINE: &str = "\\n";
 
-pub fn secondary_text_combined(primary_text: &LastTextValue, secondary_text: &str, separator: &str) -> FastStr {
-    if secondary_text != "MISSING" && !secondary_text.is_empty() {
-        format!("{}{}{}", primary_text.0, separator, secondary_text).into()
-    } else {
-        primary_text.0.clone().into() // Fallback to primary if secondary is missing/empty
+/// Appends each extra text to the primary text, every extra preceded by its separator as
+/// `normalization` adapts it to the texts around it.
+/// Missing (`MISSING`) or empty extra texts are left out together with their separator.
+pub fn texts_combined<'a>(primary_text: &LastTextValue, extras: impl IntoIterator<Item = (&'a str, &'a str)>, normalization: &Normalization) -> FastStr {
+    let mut combined = primary_text.0.to_string();
+    let mut previous: &str = &primary_text.0;
+    for (separator, text) in extras {
+        if text != "MISSING" && !text.is_empty() {
+            combined.push_str(&normalization.separator(separator, previous, text));
+            combined.push_str(text);
+            previous = text;
+        }
+    }
+    combined.into()
+}
+
+/// Same as `texts_combined` with the extra texts in front: every extra text after the first
+/// keeps its separator, the first one's separator goes before the primary text at the end.
+pub fn texts_combined_secondary_first<'a>(
+    primary_text: &LastTextValue,
+    extras: impl IntoIterator<Item = (&'a str, &'a str)>,
+    normalization: &Normalization,
+) -> FastStr {
+    let mut shown = extras.into_iter().filter(|(_, text)| *text != "MISSING" && !text.is_empty());
+    let Some((first_separator, first_text)) = shown.next() else {
+        return primary_text.0.clone();
+    };
+    let mut combined = first_text.to_string();
+    let mut previous = first_text;
+    for (separator, text) in shown {
+        combined.push_str(&normalization.separator(separator, previous, text));
+        combined.push_str(text);
+        previous = text;
     }
+    combined.push_str(&normalization.separator(first_separator, previous, &primary_text.0));
+    combined.push_str(&primary_text.0);
+    combined.into()
 }
 
-pub fn create_new_pak(files: Vec<PathBuf>, output_dir: &Path, primary_language: &str) -> Result<(), BilingualGeneratorError> {
-    let pak_name = format!("{}_xml.pak", primary_language);
-    let pak_path = output_dir.join(pak_name);
+/// Parses one localization `<Table>` and returns Entry id -> text of the third cell.
+/// Entities are unescaped, the writer in `table_xml` escapes them again.
+pub fn parse_table_xml(content: &str, context: &str) -> Result<IndexMap<EntryId, LastTextValue>, BilingualGeneratorError> {
+    let mut reader = Reader::from_str(content);
+    let mut buf = Vec::with_capacity(1024);
+    let mut single_file_data = IndexMap::new();
+    let mut current_cells: Vec<String> = Vec::with_capacity(4);
+    let mut inside_cell = false;
+
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) if e.name().as_ref() == b"Row" => current_cells.clear(),
+            // Track cells explicitly so empty cells keep their position
+            Ok(Event::Start(e)) if e.name().as_ref() == b"Cell" => {
+                inside_cell = true;
+                current_cells.push(String::new());
+            }
+            Ok(Event::Empty(e)) if e.name().as_ref() == b"Cell" => current_cells.push(String::new()),
+            Ok(Event::End(e)) if e.name().as_ref() == b"Cell" => inside_cell = false,
+            Ok(Event::End(e)) if e.name().as_ref() == b"Row" => {
+                // Cell 0 is the Entry id, cell 2 the text
+                if current_cells.len() >= 3 && current_cells[0] != "Entry id" {
+                    single_file_data.insert(EntryId(current_cells[0].clone().into()), LastTextValue(current_cells[2].clone().into()));
+                }
+                current_cells.clear();
+            }
+            Ok(Event::Text(e)) if inside_cell => {
+                let text = e
+                    .unescape()
+                    .map_err(|e| BilingualGeneratorError::XmlProcessingFailed(format!("Invalid XML escape sequence in {}: {}", context, e)))?;
+                if let Some(cell) = current_cells.last_mut() {
+                    cell.push_str(&text);
+                }
+            }
+            Ok(Event::CData(e)) if inside_cell => {
+                let text = std::str::from_utf8(&e)
+                    .map_err(|_| BilingualGeneratorError::XmlProcessingFailed(format!("Invalid UTF-8 in XML text of {}", context)))?;
+                if let Some(cell) = current_cells.last_mut() {
+                    cell.push_str(text);
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(BilingualGeneratorError::XmlProcessingFailed(format!("XML error in {}: {}", context, e))),
+            _ => {}
+        }
+    }
+    Ok(single_file_data)
+}
+
+/// Names of every XML file in `<game>/Localization/{language}_xml.pak`, in pak order.
+pub fn list_pak_xml_files(game_path: &Path, language: &str) -> Result<Vec<String>, BilingualGeneratorError> {
+    let pak_path = game_path.join("Localization").join(format!("{}_xml.pak", language));
+    let pak_file = File::open(&pak_path).map_err(|e| BilingualGeneratorError::PakOperationFailed {
+        operation: "opening PAK file".to_string(),
+        context: pak_path.display().to_string(),
+        source: e,
+    })?;
+    let archive = ZipArchive::new(pak_file).map_err(|e| BilingualGeneratorError::PakOperationFailed {
+        operation: "creating ZipArchive from PAK".to_string(),
+        context: pak_path.display().to_string(),
+        source: e.into(),
+    })?;
+    Ok((0..archive.len())
+        .filter_map(|index| archive.name_for_index(index))
+        .filter(|name| name.to_ascii_lowercase().ends_with(".xml"))
+        .map(str::to_string)
+        .collect())
+}
 
-    let file = File::create(&pak_path).map_err(|_| BilingualGeneratorError::PakCreationFailed)?;
-    let mut zip = ZipWriter::new(file);
-    let options: FileOptions<'_, ExtendedFileOptions> = FileOptions::default().compression_method(CompressionMethod::Deflated);
+/// Reads the given XML files from `<game>/Localization/{language}_xml.pak`.
+/// Files missing from the pak are skipped with a warning.
+pub fn read_language_pak(
+    game_path: &Path,
+    language: &str,
+    files_to_process: &[String],
+) -> Result<HashMap<XmlFile, IndexMap<EntryId, LastTextValue>>, BilingualGeneratorError> {
+    let pak_filename = format!("{}_xml.pak", language);
+    let pak_path = game_path.join("Localization").join(&pak_filename);
+    let pak_file = File::open(&pak_path).map_err(|e| BilingualGeneratorError::PakOperationFailed {
+        operation: "opening PAK file".to_string(),
+        context: pak_path.display().to_string(),
+        source: e,
+    })?;
+    let mut archive = ZipArchive::new(pak_file).map_err(|e| BilingualGeneratorError::PakOperationFailed {
+        operation: "creating ZipArchive from PAK".to_string(),
+        context: pak_path.display().to_string(),
+        source: e.into(),
+    })?;
 
+    let mut language_data = HashMap::new();
+    for xml_filename in files_to_process {
+        let mut xml_file = match archive.by_name(xml_filename) {
+            Ok(file) => file,
+            Err(e) => {
+                eprintln!(
+                    "[Reader: {}] Warning: Could not find {} in {}: {}. Skipping file.",
+                    language, xml_filename, pak_filename, e
+                );
+                continue;
+            }
+        };
+        let mut content = String::new();
+        xml_file.read_to_string(&mut content).map_err(|e| BilingualGeneratorError::PakOperationFailed {
+            operation: "reading XML content from PAK".to_string(),
+            context: format!("{} in {}", xml_filename, pak_filename),
+            source: e,
+        })?;
+        let context = format!("{} in {}", xml_filename, pak_filename);
+        language_data.insert(XmlFile(xml_filename.clone().into()), parse_table_xml(&content, &context)?);
+    }
+    Ok(language_data)
+}
+
+/// Languages with a `{language}_xml.pak` in `<game>/Localization`, sorted by name.
+pub fn installed_languages(game_path: &Path) -> Result<Vec<String>, BilingualGeneratorError> {
+    let localization = game_path.join("Localization");
+    let entries = std::fs::read_dir(&localization).map_err(|e| BilingualGeneratorError::PakOperationFailed {
+        operation: "listing installed language paks".to_string(),
+        context: localization.display().to_string(),
+        source: e,
+    })?;
+    let mut languages: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().to_str()?.strip_suffix("_xml.pak").map(str::to_string))
+        .collect();
+    languages.sort();
+    Ok(languages)
+}
+
+/// Packs existing XML files into `{primary_language}_xml.pak`.
+/// With `original_pak`, every entry of it that is not replaced by `files` is copied over unchanged.
+/// The generator itself writes tables straight into a `PakBuilder` instead.
+pub fn create_new_pak(files: Vec<PathBuf>, output_dir: &Path, primary_language: &str, original_pak: Option<&Path>) -> Result<(), BilingualGeneratorError> {
+    let mut pak = PakBuilder::new();
     for path in files {
         let file_name = path.file_name().ok_or(BilingualGeneratorError::PakCreationFailed)?;
         let file_name_str = file_name.to_str().ok_or(BilingualGeneratorError::PakCreationFailed)?;
-
-        zip.start_file(file_name_str, options.clone())
-            .map_err(|_| BilingualGeneratorError::PakCreationFailed)?;
         let content = std::fs::read(&path).map_err(|_| BilingualGeneratorError::PakCreationFailed)?;
-        zip.write_all(&content).map_err(|_| BilingualGeneratorError::PakCreationFailed)?;
+        pak.add_file(file_name_str, &content)?;
     }
-
-    zip.finish().map_err(|_| BilingualGeneratorError::PakCreationFailed)?;
-
+    if let Some(original_pak) = original_pak {
+        pak.copy_remaining_from(original_pak)?;
+    }
+    pak.finish(output_dir, &format!("{}_xml.pak", primary_language))?;
     Ok(())
 }
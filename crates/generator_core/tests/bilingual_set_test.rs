@@ -0,0 +1,333 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common;
+    use generator_core::{
+        bilingual_generator::{EntryId, LastTextValue},
+        bilingual_generator_errors::{BilingualGeneratorError, SetIssue},
+        bilingual_set::{BilingualPair, BilingualSetFile, load_bilingual_set, migrate_bilingual_set, parse_bilingual_set_toml},
+        util::parse_table_xml,
+    };
+    use indexmap::IndexMap;
+    use std::io::Read;
+
+    #[test]
+    fn parse_line_accepts_target_slots() {
+        let pair = BilingualPair::parse_line("Chineses + English").unwrap();
+        assert_eq!(pair, BilingualPair::new("Chineses", "English"));
+        assert_eq!(pair.pak_name(), "Chineses_xml.pak");
+        assert_eq!(pair.output_name(), "Chineses + English");
+
+        let pair = BilingualPair::parse_line("Chineses + Japanese -> English").unwrap();
+        assert_eq!(pair.slot(), "English");
+        assert_eq!(pair.pak_name(), "English_xml.pak");
+        assert_eq!(pair.output_name(), "Chineses + Japanese as English");
+
+        let pair = BilingualPair::parse_line("Chineses + English + German -> Japanese").unwrap();
+        assert_eq!(
+            pair,
+            BilingualPair::multilingual("Chineses", ["English", "German"]).with_target_slot("Japanese")
+        );
+        assert_eq!(pair.output_name(), "Chineses + English + German as Japanese");
+        assert_eq!(pair.to_string(), "Chineses + English + German -> Japanese");
+        assert_eq!(pair.languages(), vec!["Chineses", "English", "German"]);
+
+        let pair = BilingualPair::parse_line("Japanese -> English").unwrap();
+        assert!(pair.is_language_swap());
+        assert_eq!(pair.languages(), vec!["Japanese"]);
+        assert_eq!(pair.output_name(), "Japanese as English");
+        assert_eq!(pair.to_string(), "Japanese -> English");
+
+        for invalid in [
+            "English",
+            "English -> English",
+            "Chineses + -> English",
+            "Chineses + English -> ",
+            "A -> B + C",
+            "A + + B",
+        ] {
+            assert!(BilingualPair::parse_line(invalid).is_err(), "{:?} should be rejected", invalid);
+        }
+    }
+
+    #[test]
+    fn language_swap_writes_primary_text_into_target_slot() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let out = tempfile::tempdir()?;
+        let mut generator = common::fixture_generator(game.path(), out.path())?;
+        generator.read_xml_from_paks()?;
+
+        let output_dir = generator.process_single_bilingual(&BilingualPair::parse_line("Chineses -> English")?)?;
+        assert!(output_dir.ends_with("Chineses as English/Localization"));
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(output_dir.join("English_xml.pak"))?).unwrap();
+        let mut content = String::new();
+        archive.by_name("text_ui_dialog.xml").unwrap().read_to_string(&mut content)?;
+        let table = parse_table_xml(&content, "text_ui_dialog.xml")?;
+        assert_eq!(table.get(&EntryId("d_hello".into())).unwrap().0, "你好，亨利。");
+        assert_eq!(table.get(&EntryId("d_amp".into())).unwrap().0, "汤姆 & 杰瑞");
+        Ok(())
+    }
+
+    #[test]
+    fn toml_set_carries_per_pair_options() -> Result<(), BilingualGeneratorError> {
+        let pairs = parse_bilingual_set_toml(
+            r#"
+            [[pair]]
+            primary = "Chineses"
+            secondary = "German"
+            target_slot = "English"
+            include = ["text_ui_dialog.xml"]
+            fallback = "French"
+            rule_set = "learner"
+            output_name = "cn_de"
+            separators = { slash = " | " }
+            templates = { newline = "{secondary}\\n{primary}" }
+
+            [[pair]]
+            primary = "Japanese"
+            target_slot = "English"
+            "#,
+        )?;
+        assert_eq!(pairs.len(), 2);
+        let pair = &pairs[0];
+        assert_eq!(pair.pak_name(), "English_xml.pak");
+        assert_eq!(pair.output_name(), "cn_de");
+        assert_eq!(pair.fallback_languages(), vec!["French"]);
+        assert_eq!(pair.languages(), vec!["Chineses", "German", "French"]);
+        let filter = pair.file_filter()?;
+        assert!(filter.matches("text_ui_dialog.xml") && !filter.matches("text_ui_menus.xml"));
+        let separators = pair.separators.as_ref().unwrap();
+        assert_eq!((separators.slash.as_str(), separators.newline.as_str()), (" | ", "\\n"));
+        let templates = pair.templates.as_ref().unwrap();
+        assert_eq!(
+            (templates.slash.as_deref(), templates.newline.as_deref()),
+            (None, Some("{secondary}\\n{primary}"))
+        );
+        assert!(pairs[1].is_language_swap());
+
+        for invalid in [
+            "[[pair]]\nprimary = \"Chineses\"",
+            "[[pair]]\nprimary = \"Chineses\"\nsecondary = \"English\"\ncolour = \"red\"",
+            "[[pair]]\nprimary = \"Chineses\"\nsecondary = \"English\"\noutput_name = \"../out\"",
+            "[[pair]]\nprimary = \"Chineses\"\nsecondary = \"English\"\ntemplates = { slash = \"{primary}\" }",
+        ] {
+            assert!(parse_bilingual_set_toml(invalid).is_err(), "{:?} should be rejected", invalid);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn txt_set_migrates_to_equivalent_toml() -> Result<(), BilingualGeneratorError> {
+        let working_dir = tempfile::tempdir()?;
+        let assets = working_dir.path().join("assets");
+        std::fs::create_dir_all(&assets)?;
+        std::fs::write(
+            assets.join("bilingual_set.txt"),
+            "Chineses + English\n\nChineses + Japanese -> English\nJapanese -> English\n",
+        )?;
+        let from_txt = load_bilingual_set(working_dir.path())?;
+
+        let toml_path = migrate_bilingual_set(working_dir.path())?;
+        assert_eq!(toml_path, assets.join("bilingual_set.toml"));
+        // The TOML file now takes precedence and describes the same pairs
+        assert_eq!(load_bilingual_set(working_dir.path())?, from_txt);
+        // Migrating again must not overwrite the TOML file
+        assert!(migrate_bilingual_set(working_dir.path()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn pair_options_shape_the_generated_pak() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let out = tempfile::tempdir()?;
+        let rules = out.path().join("assets").join("rules");
+        std::fs::create_dir_all(&rules)?;
+        std::fs::write(
+            rules.join("brackets.toml"),
+            "[[rule]]\nname = \"all\"\naction = \"template\"\ntemplate = \"{primary} [{secondary}]\"\nfallback = true\n",
+        )?;
+        std::fs::write(
+            out.path().join("assets").join("bilingual_set.toml"),
+            "[[pair]]\nprimary = \"Chineses\"\nsecondary = \"German\"\ninclude = [\"text_ui_dialog.xml\"]\nrule_set = \"brackets\"\noutput_name = \"cn_de\"\n",
+        )?;
+        let mut generator = common::fixture_generator(game.path(), out.path())?;
+        let pairs = generator.acquire_bilingual_set()?;
+        assert!(generator.language_to_process.contains(&"German".to_string()));
+        generator.read_xml_from_paks()?;
+
+        let output_dir = generator.process_single_bilingual(&pairs[0])?;
+        assert!(output_dir.ends_with("cn_de/Localization"));
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(output_dir.join("Chineses_xml.pak"))?).unwrap();
+        // Only the dialog is generated, every other file is copied from the original pak
+        assert_eq!(archive.len(), 7);
+        assert_eq!(archive.by_index(0).unwrap().name(), "text_ui_dialog.xml");
+        let mut content = String::new();
+        archive.by_name("text_ui_dialog.xml").unwrap().read_to_string(&mut content)?;
+        let table = parse_table_xml(&content, "text_ui_dialog.xml")?;
+        assert_eq!(table.get(&EntryId("d_hello".into())).unwrap().0, "你好，亨利。 [Hallo, Heinrich.]");
+        // German lacks d_bye, the English fallback fills in
+        assert_eq!(table.get(&EntryId("d_bye".into())).unwrap().0, "再见 [Goodbye]");
+        Ok(())
+    }
+
+    #[test]
+    fn pair_styles_are_escaped_in_the_generated_xml() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let out = tempfile::tempdir()?;
+        std::fs::create_dir_all(out.path().join("assets"))?;
+        std::fs::write(
+            out.path().join("assets").join("bilingual_set.toml"),
+            "[[pair]]\nprimary = \"Chineses\"\nsecondary = \"English\"\ninclude = [\"text_ui_dialog.xml\"]\n\
+             styles = { \"text_ui_dialog.xml\" = '<font color=\"#a0a0a0\">{text}</font>' }\n",
+        )?;
+        let mut generator = common::fixture_generator(game.path(), out.path())?;
+        let pairs = generator.acquire_bilingual_set()?;
+        generator.read_xml_from_paks()?;
+
+        let output_dir = generator.process_single_bilingual(&pairs[0])?;
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(output_dir.join("Chineses_xml.pak"))?).unwrap();
+        let mut content = String::new();
+        archive.by_name("text_ui_dialog.xml").unwrap().read_to_string(&mut content)?;
+        assert!(
+            content.contains("汤姆 &amp; 杰瑞\\n&lt;font color=\"#a0a0a0\"&gt;Tom &amp; Jerry&lt;/font&gt;"),
+            "{}",
+            content
+        );
+        let table = parse_table_xml(&content, "text_ui_dialog.xml")?;
+        assert_eq!(
+            table.get(&EntryId("d_hello".into())).unwrap().0,
+            "你好，亨利。\\n<font color=\"#a0a0a0\">Hello, Henry.</font>"
+        );
+
+        let invalid = "[[pair]]\nprimary = \"Chineses\"\nsecondary = \"English\"\nstyles = { \"*\" = \"<font>{text}\" }";
+        assert!(parse_bilingual_set_toml(invalid).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn file_options_pick_base_language_and_order() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let out = tempfile::tempdir()?;
+        std::fs::create_dir_all(out.path().join("assets"))?;
+        std::fs::write(
+            out.path().join("assets").join("bilingual_set.toml"),
+            r#"
+            [[pair]]
+            primary = "Chineses"
+            secondary = "English"
+
+            [[pair.file]]
+            files = ["text_ui_menus.xml", "text_ui_quest.xml"]
+            base = "English"
+
+            [[pair.file]]
+            files = ["text_ui_dialog.xml"]
+            order = "secondary_first"
+            "#,
+        )?;
+        let mut generator = common::fixture_generator(game.path(), out.path())?;
+        let pairs = generator.acquire_bilingual_set()?;
+        let filter = pairs[0].file_filter()?;
+        assert_eq!(filter.file_options("text_ui_quest.xml").and_then(|o| o.base.as_deref()), Some("English"));
+        assert_eq!(filter.file_options("text_ui_items.xml"), None);
+        generator.read_xml_from_paks()?;
+
+        let output_dir = generator.process_single_bilingual(&pairs[0])?;
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(output_dir.join("Chineses_xml.pak"))?).unwrap();
+        let mut table = |file_name: &str| -> Result<_, BilingualGeneratorError> {
+            let mut content = String::new();
+            archive.by_name(file_name).unwrap().read_to_string(&mut content)?;
+            parse_table_xml(&content, file_name)
+        };
+        let text = |table: &IndexMap<EntryId, LastTextValue>, entry_id: &str| table.get(&EntryId(entry_id.to_string().into())).unwrap().0.to_string();
+        // English is the base of the quest file, Chinese follows it
+        assert_eq!(text(&table("text_ui_quest.xml")?, "q_side"), "Side/支线");
+        assert_eq!(text(&table("text_ui_dialog.xml")?, "d_hello"), "Hello, Henry.\\n你好，亨利。");
+        assert_eq!(text(&table("text_ui_items.xml")?, "apple_desc"), "一个红彤彤的苹果。\\nA red apple.");
+
+        // Entries the base language lacks keep the primary text instead of going missing
+        std::fs::write(
+            out.path().join("assets").join("bilingual_set.toml"),
+            "[[pair]]\nprimary = \"Chineses\"\nsecondary = \"German\"\n[[pair.file]]\nfiles = [\"text_ui_dialog.xml\"]\nbase = \"German\"\n",
+        )?;
+        let pairs = generator.acquire_bilingual_set()?;
+        generator.read_xml_from_paks()?;
+        let output_dir = generator.process_single_bilingual(&pairs[0])?;
+        let mut content = String::new();
+        zip::ZipArchive::new(std::fs::File::open(output_dir.join("Chineses_xml.pak"))?)
+            .unwrap()
+            .by_name("text_ui_dialog.xml")
+            .unwrap()
+            .read_to_string(&mut content)?;
+        let dialog = parse_table_xml(&content, "text_ui_dialog.xml")?;
+        assert_eq!(dialog.len(), 3);
+        assert_eq!(text(&dialog, "d_bye"), "再见");
+        assert_eq!(text(&dialog, "d_hello"), "Hallo, Heinrich.\\n你好，亨利。");
+
+        for invalid in [
+            "[[pair]]\nprimary = \"Chineses\"\nsecondary = \"English\"\n[[pair.file]]\nfiles = [\"a.xml\"]\nbase = \"German\"",
+            "[[pair]]\nprimary = \"Chineses\"\nsecondary = \"English\"\n[[pair.file]]\nfiles = [\"a.xml\"]",
+            "[[pair]]\nprimary = \"Chineses\"\nsecondary = \"English\"\n[[pair.file]]\nfiles = []\norder = \"secondary_first\"",
+            "[[pair]]\nprimary = \"Chineses\"\ntarget_slot = \"English\"\n[[pair.file]]\nfiles = [\"a.xml\"]\norder = \"secondary_first\"",
+        ] {
+            assert!(parse_bilingual_set_toml(invalid).is_err(), "{:?} should be rejected", invalid);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn set_issues_are_reported_with_line_numbers() {
+        let mut set = BilingualSetFile::from_txt(
+            "bilingual_set.txt",
+            "Chinese + English\nChineses + English\n\nGerman + German\nChineses English\nChineses + English\nChineses + Klingon\n",
+        );
+        set.check_languages(&["Chineses".to_string(), "English".to_string(), "German".to_string()]);
+        let Err(BilingualGeneratorError::BilingualSetIssues { issues, .. }) = set.into_pairs() else {
+            panic!("the set should be rejected");
+        };
+        // Every problem is reported, sorted by line
+        assert_eq!(issues.iter().map(|issue| issue.line).collect::<Vec<_>>(), vec![1, 4, 5, 6, 7]);
+        let message = |line: usize| &issues.iter().find(|issue: &&SetIssue| issue.line == line).unwrap().message;
+        assert!(message(1).contains("did you mean 'Chineses'?"), "{}", message(1));
+        assert!(message(4).contains("with itself"), "{}", message(4));
+        assert!(message(5).contains("found 'Chineses English'"), "{}", message(5));
+        assert!(message(6).contains("duplicates the pair on line 2"), "{}", message(6));
+        assert!(message(7).contains("installed languages: Chineses, English, German"), "{}", message(7));
+
+        // A TOML pair is reported where it starts, however its header is written
+        let set = BilingualSetFile::from_toml(
+            "bilingual_set.toml",
+            "[[pair]] # main pair\nprimary = \"Chineses\"\nsecondary = \"English\"\n\n  [[ pair ]]\nprimary = \"German\"\nsecondary = \"German\"\n",
+        );
+        assert_eq!(set.entries.iter().map(|(line, _)| *line).collect::<Vec<_>>(), vec![1, 5]);
+        assert_eq!(set.issues.iter().map(|issue| issue.line).collect::<Vec<_>>(), vec![5]);
+    }
+
+    #[test]
+    fn acquire_rejects_languages_missing_from_the_game() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let out = tempfile::tempdir()?;
+        std::fs::create_dir_all(out.path().join("assets"))?;
+        std::fs::write(
+            out.path().join("assets").join("bilingual_set.toml"),
+            "[[pair]]\nprimary = \"Chineses\"\nsecondary = \"english\"\n\n[[pair]]\nprimary = \"Chineses\"\nsecondary = \"German\"\nfallback = \"Englsh\"\n",
+        )?;
+        let mut generator = common::fixture_generator(game.path(), out.path())?;
+
+        let error = generator.acquire_bilingual_set().unwrap_err().to_string();
+        assert!(
+            error.contains("line 1: secondary language 'english' has no english_xml.pak, did you mean 'English'?"),
+            "{}",
+            error
+        );
+        assert!(error.contains("line 5: fallback language 'Englsh'"), "{}", error);
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common;
+    use generator_core::{
+        bilingual_generator::{EntryId, LastTextValue},
+        bilingual_generator_errors::BilingualGeneratorError,
+        calibration::{CalibrationTargets, calibrate, percentile},
+        combine_rules::RuleSet,
+        text_metrics::LengthMetric,
+    };
+
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        let sorted = [2, 4, 6, 7];
+        assert_eq!(percentile(&sorted, 50.0), 4);
+        assert_eq!(percentile(&sorted, 51.0), 6);
+        assert_eq!(percentile(&sorted, 100.0), 7);
+        assert_eq!(percentile(&sorted, 1.0), 2);
+        assert_eq!(percentile(&[], 50.0), 0);
+    }
+
+    #[test]
+    fn thresholds_split_entries_by_target_share() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let targets = CalibrationTargets { slash: 50.0, newline: 25.0 };
+        let files = vec!["text_ui_menus.xml".to_string()];
+        let calibration = calibrate(game.path(), "Chineses", "English", &files, LengthMetric::Chars, targets)?;
+
+        // Chinese menu lengths are 2, 4, 6 and 7
+        let menus = &calibration.files[0];
+        assert_eq!(menus.primary.count, 4);
+        assert_eq!((menus.slash_max_len, menus.newline_max_len), (Some(4), Some(6)));
+        assert_eq!(menus.shares, [50.0, 25.0, 25.0]);
+        assert!(calibration.report().contains("p50=4 p75=6 p90=7 p95=7 p100=7"), "{}", calibration.report());
+
+        let toml = calibration.to_rule_set_toml(&RuleSet::default())?;
+        let rules = RuleSet::from_toml_str(&toml)?;
+        let combine = |id: &str, primary: &str, secondary: &str| {
+            rules
+                .combine(
+                    "text_ui_menus.xml",
+                    &EntryId(id.to_string().into()),
+                    &LastTextValue(primary.to_string().into()),
+                    &[Some(secondary)],
+                    None,
+                )
+                .to_string()
+        };
+        assert_eq!(combine("ui_ok", "确定", "OK"), "确定/OK");
+        assert_eq!(combine("ui_options", "游戏选项设置", "Game Options"), "游戏选项设置\\nGame Options");
+        assert_eq!(combine("ui_lt", "小于 < 大于", "less < greater"), "小于 < 大于");
+        // Rules picking entries by id and the rules of other files come from the active rule set
+        assert_eq!(combine("ui_helpoverlay_move", "移动", "Move"), "移动");
+        assert_eq!(combine("ui_loading_hint_1", "休息", "Rest"), "休息\\nRest");
+        assert!(
+            toml.contains("name = \"dialog\"") && toml.contains("name = \"items_weapon_step_1\""),
+            "{}",
+            toml
+        );
+        assert!(!toml.contains("name = \"menus_short\""), "{}", toml);
+
+        // In display width the lengths are 4, 8, 11 and 12
+        let by_width = calibrate(game.path(), "Chineses", "English", &files, LengthMetric::Width, targets)?;
+        assert_eq!((by_width.files[0].slash_max_len, by_width.files[0].newline_max_len), (Some(8), Some(11)));
+        Ok(())
+    }
+
+    #[test]
+    fn user_rule_sets_keep_their_settings() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let files = vec!["text_ui_menus.xml".to_string()];
+        let targets = CalibrationTargets { slash: 50.0, newline: 25.0 };
+        let calibration = calibrate(game.path(), "Chineses", "English", &files, LengthMetric::Chars, targets)?;
+        let base = RuleSet::from_toml_str(
+            r#"
+            [separators]
+            slash = " | "
+
+            [[rule]]
+            name = "short"
+            files = ["text_ui_menus.xml", "text_ui_items.xml"]
+            max_primary_len = 3
+            action = "newline"
+
+            [[rule]]
+            name = "default"
+            action = "slash"
+            "#,
+        )?;
+        let rules = RuleSet::from_toml_str(&calibration.to_rule_set_toml(&base)?)?;
+        assert_eq!(rules.separators.slash, " | ");
+        let names: Vec<&str> = rules.rules.iter().map(|rule| rule.name.as_str()).collect();
+        assert_eq!(names, ["short", "menus_slash", "menus_newline", "menus", "default"]);
+        assert_eq!(rules.rules[0].files, ["text_ui_items.xml"]);
+        Ok(())
+    }
+
+    #[test]
+    fn targets_and_rule_set_name_are_checked() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let files = vec!["text_ui_items.xml".to_string()];
+        let too_much = CalibrationTargets { slash: 80.0, newline: 30.0 };
+        assert!(calibrate(game.path(), "Chineses", "English", &files, LengthMetric::Chars, too_much).is_err());
+
+        let calibration = calibrate(game.path(), "Chineses", "English", &files, LengthMetric::Chars, CalibrationTargets::default())?;
+        let working_dir = tempfile::tempdir()?;
+        let path = calibration.write_rule_set(working_dir.path(), "calibrated", &RuleSet::default())?;
+        assert!(RuleSet::load_named(working_dir.path(), "calibrated").is_ok());
+        // Never overwrites, and the name stays inside assets/rules
+        assert!(calibration.write_rule_set(working_dir.path(), "calibrated", &RuleSet::default()).is_err());
+        assert!(calibration.write_rule_set(working_dir.path(), "../calibrated", &RuleSet::default()).is_err());
+        assert!(path.ends_with("assets/rules/calibrated.toml"));
+        Ok(())
+    }
+}
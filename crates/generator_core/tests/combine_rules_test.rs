@@ -0,0 +1,329 @@
+#[cfg(test)]
+mod tests {
+    use generator_core::{
+        bilingual_generator::{EntryId, LastTextValue},
+        bilingual_generator_errors::BilingualGeneratorError,
+        combine_rules::{RuleSet, Styles, Templates, validate_style, validate_template},
+    };
+
+    fn combine(rules: &RuleSet, file: &str, id: &str, primary: &str, secondary: Option<&str>, fallback: Option<&str>) -> String {
+        rules
+            .combine(
+                file,
+                &EntryId(id.to_string().into()),
+                &LastTextValue(primary.to_string().into()),
+                &[secondary],
+                fallback,
+            )
+            .to_string()
+    }
+
+    #[test]
+    fn default_rules_match_original_behaviour() {
+        let rules = RuleSet::default();
+        // menus
+        assert_eq!(
+            combine(&rules, "text_ui_menus.xml", "ui_helpoverlay_x", "帮助帮助帮助帮助", Some("Help"), None),
+            "帮助帮助帮助帮助"
+        );
+        assert_eq!(
+            combine(&rules, "text_ui_menus.xml", "ui_loading_1", "加载", Some("Loading"), None),
+            "加载\\nLoading"
+        );
+        assert_eq!(combine(&rules, "text_ui_menus.xml", "ui_ok", "确定", Some("OK"), None), "确定");
+        assert_eq!(
+            combine(&rules, "text_ui_menus.xml", "ui_state_health_desc", "生命值生命值生命值", Some("Health"), None),
+            "生命值生命值生命值"
+        );
+        assert_eq!(
+            combine(&rules, "text_ui_menus.xml", "ui_x", "一二三四五六七", Some("Seven"), None),
+            "一二三四五六七/Seven"
+        );
+        assert_eq!(
+            combine(
+                &rules,
+                "text_ui_menus.xml",
+                "ui_x",
+                "一二三四五六七八九十一二三四五六七八九十",
+                Some("Long"),
+                None
+            ),
+            "一二三四五六七八九十一二三四五六七八九十\\nLong"
+        );
+        // dialog falls back to English
+        assert_eq!(combine(&rules, "text_ui_dialog.xml", "d1", "你好", None, Some("Hello")), "你好\\nHello");
+        // items
+        assert_eq!(
+            combine(&rules, "text_ui_items.xml", "sword_step_2", "一二三四五六七八九十", Some("S"), None),
+            "一二三四五六七八九十"
+        );
+        assert_eq!(combine(&rules, "text_ui_items.xml", "bane_step_1", "短", Some("S"), None), "短");
+        assert_eq!(combine(&rules, "text_ui_items.xml", "apple", "苹果", Some("Apple"), None), "苹果/Apple");
+        assert_eq!(
+            combine(&rules, "text_ui_items.xml", "apple_desc", "一二三四五六七", Some("Apple"), None),
+            "一二三四五六七\\nApple"
+        );
+        // soul
+        assert_eq!(
+            combine(&rules, "text_ui_soul.xml", "stat_str", "力量力量力量力量", Some("Strength"), None),
+            "力量力量力量力量/Strength"
+        );
+        assert_eq!(
+            combine(&rules, "text_ui_soul.xml", "buff_drunkenness_desc", "一二三四五六七八", Some("Drunk"), None),
+            "一二三四五六七八"
+        );
+        assert_eq!(
+            combine(&rules, "text_ui_soul.xml", "perk_x_desc", "一二三四五六七八", Some("Perk"), None),
+            "一二三四五六七八\\nPerk"
+        );
+        // other files
+        assert_eq!(combine(&rules, "text_ui_quest.xml", "q1", "任务", Some("Quest"), None), "任务/Quest");
+        // missing secondary without fallback keeps the primary
+        assert_eq!(combine(&rules, "text_ui_items.xml", "apple", "苹果", None, Some("Apple")), "苹果");
+    }
+
+    #[test]
+    fn custom_rules_are_ordered_and_support_regex_and_templates() -> Result<(), BilingualGeneratorError> {
+        let rules = RuleSet::from_toml_str(
+            r#"
+            [[rule]]
+            name = "numbered"
+            id_regex = "^line_\\d+$"
+            action = "template"
+            template = "{primary} ({secondary})"
+
+            [[rule]]
+            name = "short_secondary"
+            max_secondary_len = 3
+            action = "slash"
+
+            [[rule]]
+            name = "rest"
+            action = "primary_only"
+            "#,
+        )?;
+        assert_eq!(combine(&rules, "a.xml", "line_12", "你好", Some("Hello"), None), "你好 (Hello)");
+        assert_eq!(combine(&rules, "a.xml", "other", "是", Some("Yes"), None), "是/Yes");
+        assert_eq!(combine(&rules, "a.xml", "other", "你好", Some("Hello"), None), "你好");
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        assert!(RuleSet::from_toml_str("[[rule]]\nname = \"x\"\naction = \"template\"").is_err());
+        assert!(RuleSet::from_toml_str("[[rule]]\nname = \"x\"\nid_regex = \"(\"\naction = \"slash\"").is_err());
+        assert!(RuleSet::from_toml_str("[[rule]]\nname = \"x\"\naction = \"explode\"").is_err());
+    }
+
+    #[test]
+    fn templates_replace_action_separators() -> Result<(), BilingualGeneratorError> {
+        let rules = RuleSet::from_toml_str(
+            r#"
+            [templates]
+            slash = "{primary}（{secondary}）"
+            newline = "{secondary}\\n{primary}"
+
+            [[rule]]
+            name = "menus"
+            files = ["text_ui_menus.xml"]
+            action = "slash"
+
+            [[rule]]
+            name = "dialog"
+            files = ["text_ui_dialog.xml"]
+            action = "newline"
+
+            [[rule]]
+            name = "own_separator"
+            action = "slash"
+            separators = [" — "]
+            "#,
+        )?;
+        assert_eq!(combine(&rules, "text_ui_menus.xml", "ui_ok", "确定", Some("OK"), None), "确定（OK）");
+        assert_eq!(combine(&rules, "text_ui_dialog.xml", "d_hello", "你好", Some("Hello"), None), "Hello\\n你好");
+        // A rule's own separators win over the rule set's template
+        assert_eq!(combine(&rules, "a.xml", "x", "你好", Some("Hello"), None), "你好 — Hello");
+        // Without a secondary text the primary text stays alone
+        assert_eq!(combine(&rules, "text_ui_menus.xml", "ui_ok", "确定", None, None), "确定");
+        Ok(())
+    }
+
+    #[test]
+    fn secondary_first_order_puts_extra_texts_in_front() -> Result<(), BilingualGeneratorError> {
+        let rules = RuleSet::from_toml_str(
+            r#"
+            order = "secondary_first"
+
+            [[rule]]
+            name = "dialog"
+            files = ["text_ui_dialog.xml"]
+            action = "newline"
+            separators = ["\\n", " | "]
+
+            [[rule]]
+            name = "rest"
+            action = "slash"
+            "#,
+        )?;
+        assert_eq!(combine(&rules, "a.xml", "x", "是", Some("Yes"), None), "Yes/是");
+        assert_eq!(combine(&rules, "a.xml", "x", "是", None, None), "是");
+        let combined = rules.combine(
+            "text_ui_dialog.xml",
+            &EntryId("d_hello".into()),
+            &LastTextValue("你好".into()),
+            &[Some("Hello"), Some("Hallo")],
+            None,
+        );
+        assert_eq!(combined, "Hello | Hallo\\n你好");
+        Ok(())
+    }
+
+    #[test]
+    fn rules_abbreviate_long_secondary_texts() -> Result<(), BilingualGeneratorError> {
+        let rules = RuleSet::from_toml_str(
+            r#"
+            [[rule]]
+            name = "perk_desc"
+            id_contains = ["_desc"]
+            action = "newline"
+            abbreviate_to_sentence = true
+            abbreviate_to_width = 20
+
+            [[rule]]
+            name = "step"
+            id_contains = ["step"]
+            action = "slash"
+            abbreviate_to_width = 6
+            "#,
+        )?;
+        assert_eq!(
+            combine(&rules, "a.xml", "perk_desc", "恢复体力。", Some("Restores stamina. Lasts an hour."), None),
+            "恢复体力。\\nRestores stamina…"
+        );
+        assert_eq!(
+            combine(&rules, "a.xml", "perk_desc", "恢复体力。", Some("Restores all stamina slowly."), None),
+            "恢复体力。\\nRestores all…"
+        );
+        assert_eq!(combine(&rules, "a.xml", "sword_step_2", "第二步", Some("Step two"), None), "第二步/Step…");
+        assert_eq!(combine(&rules, "a.xml", "sword_step_2", "第二步", Some("Step"), None), "第二步/Step");
+        assert!(RuleSet::from_toml_str("[[rule]]\nname = \"x\"\naction = \"slash\"\nabbreviate_to_width = 0").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn styles_wrap_extra_texts_per_file() -> Result<(), BilingualGeneratorError> {
+        let rules = RuleSet::from_toml_str(
+            r##"
+            [styles]
+            "text_ui_dialog.xml" = '<font color="#a0a0a0">{text}</font>'
+            "*" = "<font size='18'>{text}</font>"
+
+            [[rule]]
+            name = "dialog"
+            files = ["text_ui_dialog.xml"]
+            action = "newline"
+
+            [[rule]]
+            name = "short"
+            max_primary_len = 2
+            action = "slash"
+
+            [[rule]]
+            name = "rest"
+            action = "primary_only"
+            "##,
+        )?;
+        assert_eq!(
+            combine(&rules, "text_ui_dialog.xml", "d_hello", "你好", Some("Hello"), None),
+            "你好\\n<font color=\"#a0a0a0\">Hello</font>"
+        );
+        assert_eq!(combine(&rules, "a.xml", "x", "是", Some("Yes"), None), "是/<font size='18'>Yes</font>");
+        // Length conditions look at the text, not the markup
+        assert_eq!(combine(&rules, "a.xml", "x", "你好吗", Some("Yes"), None), "你好吗");
+        // A missing secondary text gets no empty markup
+        assert_eq!(combine(&rules, "a.xml", "x", "是", None, None), "是");
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_styles_are_rejected() {
+        assert!(validate_style("<font color=\"#a0a0a0\">{text}</font>").is_ok());
+        assert!(validate_style("<b><i>{text}</i></b><br/>").is_ok());
+        for (style, reason) in [
+            ("<font color=\"#a0a0a0\">text</font>", "needs {text} exactly once"),
+            ("<font>{text}</font>{text}", "needs {text} exactly once"),
+            ("<font color=\"#a0a0a0\">{text}", "never closes <font>"),
+            ("<b><i>{text}</b></i>", "closes <b> without opening it"),
+            ("{text}\u{0}", "not allowed in XML"),
+        ] {
+            let error = validate_style(style).unwrap_err();
+            assert!(error.contains(reason), "{:?}: {}", style, error);
+        }
+        let styles = Styles([("text_ui_dialog.xml".to_string(), "{text}</font>".to_string())].into());
+        assert!(styles.validate().unwrap_err().starts_with("style of text_ui_dialog.xml: "));
+        assert!(RuleSet::from_toml_str("[styles]\n\"*\" = \"<b>\"").is_err());
+    }
+
+    #[test]
+    fn invalid_templates_are_rejected() {
+        assert!(validate_template("{primary} — {secondary}").is_ok());
+        assert!(validate_template("{secondary_2}: {primary}").is_ok());
+        for (template, reason) in [
+            ("{primary} only", "needs both"),
+            ("({secondary})", "needs both"),
+            ("{primary} ({secondry})", "unknown placeholder {secondry}"),
+            ("{primary} ({secondary_0})", "unknown placeholder {secondary_0}"),
+            ("{primary}\u{1}{secondary}", "not allowed in XML"),
+        ] {
+            let error = validate_template(template).unwrap_err();
+            assert!(error.contains(reason), "{:?}: {}", template, error);
+        }
+        let templates = Templates {
+            slash: None,
+            newline: Some("{primary}".to_string()),
+        };
+        assert!(templates.validate().unwrap_err().starts_with("newline template: "));
+        assert!(RuleSet::from_toml_str("[templates]\nslash = \"{primary}/\"").is_err());
+        assert!(RuleSet::from_toml_str("[[rule]]\nname = \"x\"\naction = \"template\"\ntemplate = \"[{primary}]\"").is_err());
+    }
+
+    #[test]
+    fn rules_choose_extra_languages_and_their_separators() -> Result<(), BilingualGeneratorError> {
+        let rules = RuleSet::from_toml_str(
+            r#"
+            [[rule]]
+            name = "menus_first_extra_only"
+            files = ["text_ui_menus.xml"]
+            action = "slash"
+            max_extra = 1
+
+            [[rule]]
+            name = "dialog_all"
+            files = ["text_ui_dialog.xml"]
+            action = "newline"
+            separators = ["\\n", " | "]
+            fallback = true
+
+            [[rule]]
+            name = "numbered"
+            action = "template"
+            template = "{primary} ({secondary_2}; {secondary}{secondary_3})"
+            "#,
+        )?;
+        let combine = |file: &str, secondaries: &[Option<&str>], fallback: Option<&str>| {
+            rules
+                .combine(file, &EntryId("id".into()), &LastTextValue("你好".into()), secondaries, fallback)
+                .to_string()
+        };
+        assert_eq!(combine("text_ui_menus.xml", &[Some("Hello"), Some("Hallo")], None), "你好/Hello");
+        assert_eq!(
+            combine("text_ui_dialog.xml", &[Some("Hello"), Some("Hallo"), Some("Salut")], None),
+            "你好\\nHello | Hallo | Salut"
+        );
+        // A missing extra text falls back, but a text already shown is not repeated
+        assert_eq!(combine("text_ui_dialog.xml", &[Some("Hello"), None], Some("Hello")), "你好\\nHello");
+        assert_eq!(combine("text_ui_dialog.xml", &[None, Some("Hallo")], Some("Hello")), "你好\\nHello | Hallo");
+        assert_eq!(combine("text_ui_items.xml", &[Some("Hello"), Some("Hallo")], None), "你好 (Hallo; Hello)");
+        Ok(())
+    }
+}
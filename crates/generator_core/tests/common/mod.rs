@@ -0,0 +1,156 @@
+// Shared fixtures: builds small localization paks in a temporary game directory.
+#![allow(dead_code)]
+
+use generator_core::{bilingual_generator::BilingualGenerator, bilingual_generator_errors::BilingualGeneratorError};
+use std::{io::Write, path::Path};
+use zip::{ZipWriter, write::SimpleFileOptions};
+
+/// Languages of the fixture game.
+pub const LANGUAGES: [&str; 3] = ["Chineses", "English", "German"];
+
+pub const FILES: [&str; 6] = [
+    "text_ui_dialog.xml",
+    "text_ui_quest.xml",
+    "text_ui_tutorials.xml",
+    "text_ui_soul.xml",
+    "text_ui_items.xml",
+    "text_ui_menus.xml",
+];
+
+/// Builds a `<Table>` in the game's layout: header row, then Entry id / original / translated cells.
+pub fn table_xml(rows: &[(&str, &str)]) -> String {
+    let mut xml = String::from("<Table>\n<Row><Cell>Entry id</Cell><Cell>Original</Cell><Cell>Translated</Cell></Row>\n");
+    for (id, text) in rows {
+        xml.push_str(&format!("<Row><Cell>{}</Cell><Cell>{}</Cell><Cell>{}</Cell></Row>\n", id, text, text));
+    }
+    xml.push_str("</Table>");
+    xml
+}
+
+/// Writes `<game_path>/Localization/{language}_xml.pak` containing the given files.
+pub fn write_pak(game_path: &Path, language: &str, files: &[(&str, String)]) {
+    let localization = game_path.join("Localization");
+    std::fs::create_dir_all(&localization).unwrap();
+    let file = std::fs::File::create(localization.join(format!("{}_xml.pak", language))).unwrap();
+    let mut zip = ZipWriter::new(file);
+    for (name, content) in files {
+        zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+/// Rows of every fixture file for one language.
+pub fn language_rows(language: &str) -> Vec<(&'static str, Vec<(&'static str, &'static str)>)> {
+    match language {
+        "Chineses" => vec![
+            (
+                "text_ui_dialog.xml",
+                vec![("d_hello", "你好，亨利。"), ("d_bye", "再见"), ("d_amp", "汤姆 &amp; 杰瑞")],
+            ),
+            ("text_ui_quest.xml", vec![("q_main", "主线任务"), ("q_side", "支线")]),
+            ("text_ui_tutorials.xml", vec![("t_move", "移动")]),
+            (
+                "text_ui_soul.xml",
+                vec![
+                    ("stat_str", "力量"),
+                    ("buff_drunk_desc", "你喝醉了，走路摇摇晃晃。"),
+                    ("soul_long", "一段很长很长的描述文字"),
+                ],
+            ),
+            (
+                "text_ui_items.xml",
+                vec![
+                    ("apple", "苹果"),
+                    ("apple_desc", "一个红彤彤的苹果。"),
+                    ("sword_step_2", "一二三四五六七八九十"),
+                ],
+            ),
+            (
+                "text_ui_menus.xml",
+                vec![
+                    ("ui_ok", "确定"),
+                    ("ui_options", "游戏选项设置"),
+                    ("ui_helpoverlay_a", "帮助说明"),
+                    ("ui_lt", "小于 &lt; 大于"),
+                ],
+            ),
+            // Not one of the six original files, e.g. added by a patch
+            ("text_ui_ingame.xml", vec![("ig_saved", "游戏已保存")]),
+        ],
+        "English" => vec![
+            (
+                "text_ui_dialog.xml",
+                vec![("d_hello", "Hello, Henry."), ("d_bye", "Goodbye"), ("d_amp", "Tom &amp; Jerry")],
+            ),
+            ("text_ui_quest.xml", vec![("q_main", "Main Quest"), ("q_side", "Side")]),
+            ("text_ui_tutorials.xml", vec![("t_move", "Move")]),
+            (
+                "text_ui_soul.xml",
+                vec![
+                    ("stat_str", "Strength"),
+                    ("buff_drunk_desc", "You are drunk."),
+                    ("soul_long", "A very long description"),
+                ],
+            ),
+            (
+                "text_ui_items.xml",
+                vec![("apple", "Apple"), ("apple_desc", "A red apple."), ("sword_step_2", "Step two")],
+            ),
+            (
+                "text_ui_menus.xml",
+                vec![
+                    ("ui_ok", "OK"),
+                    ("ui_options", "Game Options"),
+                    ("ui_helpoverlay_a", "Help"),
+                    ("ui_lt", "less &lt; greater"),
+                ],
+            ),
+            ("text_ui_ingame.xml", vec![("ig_saved", "Game saved")]),
+        ],
+        "German" => vec![
+            // d_bye is missing on purpose: dialog falls back to English
+            ("text_ui_dialog.xml", vec![("d_hello", "Hallo, Heinrich."), ("d_amp", "Tom &amp; Jerry")]),
+            ("text_ui_quest.xml", vec![("q_main", "Hauptquest"), ("q_side", "Neben")]),
+            ("text_ui_tutorials.xml", vec![("t_move", "Bewegen")]),
+            (
+                "text_ui_soul.xml",
+                vec![
+                    ("stat_str", "Stärke"),
+                    ("buff_drunk_desc", "Du bist betrunken."),
+                    ("soul_long", "Eine lange Beschreibung"),
+                ],
+            ),
+            (
+                "text_ui_items.xml",
+                vec![("apple", "Apfel"), ("apple_desc", "Ein roter Apfel."), ("sword_step_2", "Schritt zwei")],
+            ),
+            (
+                "text_ui_menus.xml",
+                vec![
+                    ("ui_ok", "OK"),
+                    ("ui_options", "Spieloptionen"),
+                    ("ui_helpoverlay_a", "Hilfe"),
+                    ("ui_lt", "kleiner &lt; größer"),
+                ],
+            ),
+        ],
+        _ => vec![],
+    }
+}
+
+/// Creates a fake game directory with Chineses, English and German paks.
+pub fn fixture_game(game_path: &Path) {
+    for language in LANGUAGES {
+        let files: Vec<(&str, String)> = language_rows(language).into_iter().map(|(name, rows)| (name, table_xml(&rows))).collect();
+        write_pak(game_path, language, &files);
+    }
+}
+
+/// A generator reading the game under `game_path` and writing to `working_dir`.
+pub fn fixture_generator(game_path: &Path, working_dir: &Path) -> Result<BilingualGenerator, BilingualGeneratorError> {
+    let mut generator = BilingualGenerator::init()?;
+    generator.game_path = game_path.to_path_buf();
+    generator.working_dir = working_dir.to_path_buf();
+    Ok(generator)
+}
@@ -14,11 +14,12 @@ mod tests {
 
         let mut xml_output_set: Vec<PathBuf> = vec![];
         let xml_output_dir = generator.working_dir.join("bilingual_xml");
-        for file_name in generator.files_to_process {
+        for file_name in generator.discover_files(["Chineses"])? {
             let xml_output_path = xml_output_dir.join(file_name);
             xml_output_set.push(xml_output_path.clone());
         }
-        let result = create_new_pak(xml_output_set, &generator.working_dir.join("bilingual_xml"), "Chineses")
+        let original_pak = generator.game_path.join("Localization").join("Chineses_xml.pak");
+        let result = create_new_pak(xml_output_set, &generator.working_dir.join("bilingual_xml"), "Chineses", Some(&original_pak))
             .map_err(|_| BilingualGeneratorError::PakCreationFailed);
         match result {
             Ok(_) => Ok(()),
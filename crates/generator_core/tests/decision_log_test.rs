@@ -0,0 +1,162 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common;
+    use generator_core::{
+        bilingual_generator_errors::BilingualGeneratorError,
+        bilingual_set::BilingualPair,
+        combine_rules::RuleSet,
+        decision_log::{DECISION_LOG_CSV, Decision, explain_entry, read_decision_log, write_decision_log},
+        overrides::Overrides,
+        text_metrics::{LengthMetric, Redundancy},
+    };
+
+    const HEADER_LINE: &str = "file,entry_id,primary_len,secondary_len,length_metric,decided_by,text";
+
+    fn decision<'a>(decisions: &'a [Decision], file: &str, entry_id: &str) -> &'a Decision {
+        decisions
+            .iter()
+            .find(|decision| decision.file == file && decision.entry_id == entry_id)
+            .unwrap_or_else(|| panic!("no decision for {}:{}", file, entry_id))
+    }
+
+    #[test]
+    fn csv_round_trips_quotes_commas_and_line_breaks() -> Result<(), BilingualGeneratorError> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(DECISION_LOG_CSV);
+        let decisions = vec![
+            Decision {
+                file: "text_ui_dialog.xml".to_string(),
+                entry_id: "d_hello".to_string(),
+                primary_len: 6,
+                secondary_len: Some(13),
+                length_metric: LengthMetric::Chars,
+                decided_by: "dialog".to_string(),
+                text: "你好，亨利。\\nHello, \"Henry\".\nSecond line".to_string(),
+            },
+            Decision {
+                file: "text_ui_menus.xml".to_string(),
+                entry_id: "ui_ok".to_string(),
+                primary_len: 2,
+                secondary_len: None,
+                length_metric: LengthMetric::Width,
+                decided_by: "no rule".to_string(),
+                text: String::new(),
+            },
+        ];
+        write_decision_log(&path, &decisions)?;
+        assert!(std::fs::read_to_string(&path)?.starts_with(&format!("{}\n", HEADER_LINE)));
+        assert_eq!(read_decision_log(&path)?, decisions);
+
+        for invalid in ["a,b,not a number,,chars,c,d", "a,b,1,,pixels,c,d"] {
+            std::fs::write(&path, format!("{}\n{}\n", HEADER_LINE, invalid))?;
+            assert!(matches!(read_decision_log(&path), Err(BilingualGeneratorError::InvalidDecisionLog(_))));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn every_entry_records_the_rule_that_decided_it() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let out = tempfile::tempdir()?;
+        let mut generator = common::fixture_generator(game.path(), out.path())?;
+        generator.language_to_process = common::LANGUAGES.map(String::from).to_vec();
+        generator.decision_log = true;
+        generator.overrides = Overrides::from_toml_str("[ids]\napple = \"slash\"")?;
+        generator.read_xml_from_paks()?;
+
+        let pair = BilingualPair::new("Chineses", "English");
+        let report = generator.process_single_bilingual_report(&pair)?;
+        let log = report.decision_log.clone().unwrap();
+        assert_eq!(log, out.path().join("bilingual_xml").join("Chineses + English").join(DECISION_LOG_CSV));
+        assert!(report.summary(&pair).contains("Decision log: "));
+
+        let decisions = read_decision_log(&log)?;
+        let ui_ok = decision(&decisions, "text_ui_menus.xml", "ui_ok");
+        assert_eq!((ui_ok.decided_by.as_str(), ui_ok.text.as_str()), ("menus_short", "确定"));
+        assert_eq!((ui_ok.primary_len, ui_ok.secondary_len), (2, Some(2)));
+        let d_hello = decision(&decisions, "text_ui_dialog.xml", "d_hello");
+        assert_eq!((d_hello.decided_by.as_str(), d_hello.text.as_str()), ("dialog", "你好，亨利。\\nHello, Henry."));
+        let apple = decision(&decisions, "text_ui_items.xml", "apple");
+        assert_eq!((apple.decided_by.as_str(), apple.text.as_str()), ("override", "苹果/Apple"));
+
+        let swap = generator.process_single_bilingual_report(&BilingualPair::parse_line("Chineses -> English")?)?;
+        let decisions = read_decision_log(&swap.decision_log.unwrap())?;
+        assert!(decisions.iter().all(|decision| decision.decided_by == "language swap"));
+
+        // Lengths are measured like the deciding rule measures them
+        generator.rule_set =
+            RuleSet::from_toml_str("[[rule]]\nname = \"wide\"\nfiles = [\"text_ui_menus.xml\"]\nlength_metric = \"width\"\naction = \"slash\"")?;
+        let decisions = read_decision_log(&generator.process_single_bilingual_report(&pair)?.decision_log.unwrap())?;
+        let ui_ok = decision(&decisions, "text_ui_menus.xml", "ui_ok");
+        assert_eq!((ui_ok.primary_len, ui_ok.secondary_len, ui_ok.length_metric), (4, Some(2), LengthMetric::Width));
+
+        // Logging is off by default, and a run without it removes the previous log
+        generator.decision_log = false;
+        assert_eq!(generator.process_single_bilingual_report(&pair)?.decision_log, None);
+        assert!(!log.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn redundant_secondary_texts_are_suppressed_and_counted() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let out = tempfile::tempdir()?;
+        let mut generator = common::fixture_generator(game.path(), out.path())?;
+        generator.language_to_process = common::LANGUAGES.map(String::from).to_vec();
+        generator.decision_log = true;
+        generator.read_xml_from_paks()?;
+
+        let pair = BilingualPair::new("German", "English");
+        let report = generator.process_single_bilingual_report(&pair)?;
+        assert_eq!(report.suppressed, [2, 0, 0]);
+        assert!(
+            report.summary(&pair).contains("2 redundant combination(s) suppressed: 2 identical"),
+            "{}",
+            report.summary(&pair)
+        );
+
+        let decisions = read_decision_log(&report.decision_log.unwrap())?;
+        let d_amp = decision(&decisions, "text_ui_dialog.xml", "d_amp");
+        assert_eq!((d_amp.decided_by.as_str(), d_amp.text.as_str()), (Redundancy::Identical.name(), "Tom & Jerry"));
+        assert_eq!(decision(&decisions, "text_ui_menus.xml", "ui_ok").text, "OK");
+        // Different texts are still combined
+        assert_eq!(decision(&decisions, "text_ui_menus.xml", "ui_options").text, "Spieloptionen / Game Options");
+        let report = generator.process_single_bilingual_report(&BilingualPair::new("Chineses", "English"))?;
+        assert_eq!(report.suppressed, [0, 0, 0]);
+
+        // German lacks d_bye, so the dialog would repeat the English text as its fallback
+        let report = generator.process_single_bilingual_report(&BilingualPair::new("English", "German"))?;
+        let decisions = read_decision_log(&report.decision_log.unwrap())?;
+        let d_bye = decision(&decisions, "text_ui_dialog.xml", "d_bye");
+        assert_eq!((d_bye.decided_by.as_str(), d_bye.text.as_str()), (Redundancy::Identical.name(), "Goodbye"));
+        // ui_ok and d_amp, plus the fallbacks of d_bye and of ig_saved, German lacks the whole file
+        assert_eq!(report.suppressed, [4, 0, 0]);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn explain_finds_an_entry_in_every_logged_pair() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let out = tempfile::tempdir()?;
+        let mut generator = common::fixture_generator(game.path(), out.path())?;
+        generator.language_to_process = common::LANGUAGES.map(String::from).to_vec();
+        generator.decision_log = true;
+        let pairs = vec![BilingualPair::new("Chineses", "English"), BilingualPair::new("Chineses", "German")];
+        let messages = generator.generate_bilingual_pairs_async(pairs).await?;
+        assert!(messages.iter().all(|message| message.contains("Decision log: ")), "{:?}", messages);
+
+        let found = explain_entry(out.path(), "d_hello", None)?;
+        let pair_names: Vec<&str> = found.iter().map(|(pair, _)| pair.as_str()).collect();
+        assert_eq!(pair_names, vec!["Chineses + English", "Chineses + German"]);
+        assert_eq!(found[1].1.text, "你好，亨利。\\nHallo, Heinrich.");
+
+        assert!(explain_entry(out.path(), "d_hello", Some("text_ui_menus.xml"))?.is_empty());
+        assert!(explain_entry(out.path(), "no_such_entry", None)?.is_empty());
+        Ok(())
+    }
+}
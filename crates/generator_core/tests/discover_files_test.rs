@@ -0,0 +1,86 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common;
+    use generator_core::{
+        bilingual_generator::{BilingualGenerator, EntryId},
+        bilingual_generator_errors::BilingualGeneratorError,
+        bilingual_set::parse_bilingual_set_toml,
+        util::parse_table_xml,
+    };
+    use std::io::Read;
+
+    fn generator_with_set(set: &str) -> Result<(BilingualGenerator, tempfile::TempDir, tempfile::TempDir), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let out = tempfile::tempdir()?;
+        std::fs::create_dir_all(out.path().join("assets"))?;
+        std::fs::write(out.path().join("assets").join("bilingual_set.toml"), set)?;
+        let generator = common::fixture_generator(game.path(), out.path())?;
+        Ok((generator, game, out))
+    }
+
+    #[test]
+    fn every_xml_file_of_the_primary_pak_is_generated() -> Result<(), BilingualGeneratorError> {
+        let (mut generator, _game, _out) = generator_with_set("[[pair]]\nprimary = \"Chineses\"\nsecondary = \"German\"\nexclude = [\"*_menus.xml\"]\n")?;
+        let pairs = generator.acquire_bilingual_set()?;
+        assert_eq!(generator.files_to_process.len(), 7);
+        assert!(generator.files_to_process.contains(&"text_ui_ingame.xml".to_string()));
+        generator.read_xml_from_paks()?;
+
+        let output_dir = generator.process_single_bilingual(&pairs[0])?;
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(output_dir.join("Chineses_xml.pak"))?).unwrap();
+        let names: Vec<&str> = archive.file_names().collect();
+        assert_eq!(names.len(), 7);
+        // The excluded menus are copied unchanged from the original pak
+        let mut menus = String::new();
+        archive.by_name("text_ui_menus.xml").unwrap().read_to_string(&mut menus)?;
+        assert!(menus.contains("<Cell>确定</Cell><Cell>确定</Cell>"), "{}", menus);
+        // Unknown files use the default rule; German lacks the file, so English fills in
+        let mut content = String::new();
+        archive.by_name("text_ui_ingame.xml").unwrap().read_to_string(&mut content)?;
+        let table = parse_table_xml(&content, "text_ui_ingame.xml")?;
+        assert_eq!(table.get(&EntryId("ig_saved".into())).unwrap().0, "游戏已保存/Game saved");
+        Ok(())
+    }
+
+    #[test]
+    fn discovered_files_without_entries_are_left_alone() -> Result<(), BilingualGeneratorError> {
+        let (mut generator, game, _out) = generator_with_set("[[pair]]\nprimary = \"Chineses\"\nsecondary = \"English\"\n")?;
+        // A table without entries, written the way the game's tools write it
+        let empty_table = "<Table>\r\n  <Row><Cell>Entry id</Cell><Cell>Original</Cell><Cell>Translated</Cell></Row>\r\n</Table>\r\n";
+        let mut files: Vec<(&str, String)> = common::language_rows("Chineses")
+            .into_iter()
+            .map(|(name, rows)| (name, common::table_xml(&rows)))
+            .collect();
+        files.push(("text_ui_empty.xml", empty_table.to_string()));
+        common::write_pak(game.path(), "Chineses", &files);
+
+        let pairs = generator.acquire_bilingual_set()?;
+        assert!(generator.files_to_process.contains(&"text_ui_empty.xml".to_string()));
+        generator.read_xml_from_paks()?;
+        let output_dir = generator.process_single_bilingual(&pairs[0])?;
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(output_dir.join("Chineses_xml.pak"))?).unwrap();
+        let mut content = String::new();
+        archive.by_name("text_ui_empty.xml").unwrap().read_to_string(&mut content)?;
+        assert_eq!(content, empty_table);
+        Ok(())
+    }
+
+    #[test]
+    fn include_and_exclude_globs_select_files() -> Result<(), BilingualGeneratorError> {
+        let pairs = parse_bilingual_set_toml(
+            "[[pair]]\nprimary = \"Chineses\"\nsecondary = \"English\"\ninclude = [\"text_ui_*.xml\"]\nexclude = [\"*_soul.xml\", \"text_ui_me?us.xml\"]\n",
+        )?;
+        let filter = pairs[0].file_filter()?;
+        assert!(filter.matches("text_ui_dialog.xml"));
+        assert!(!filter.matches("text_ui_soul.xml"));
+        assert!(!filter.matches("text_ui_menus.xml"));
+        assert!(!filter.matches("text_subtitles.xml"));
+
+        assert!(parse_bilingual_set_toml("[[pair]]\nprimary = \"Chineses\"\nsecondary = \"English\"\ninclude = [\"text_[ui.xml\"]\n").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common;
+    use generator_core::{
+        bilingual_generator::EntryId,
+        bilingual_generator_errors::BilingualGeneratorError,
+        bilingual_set::{BilingualPair, parse_bilingual_set_toml},
+        util::parse_table_xml,
+    };
+    use std::{io::Read, path::Path};
+
+    fn dialog_text(pak: &Path, entry_id: &str) -> Result<String, BilingualGeneratorError> {
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(pak)?).unwrap();
+        let mut content = String::new();
+        archive.by_name("text_ui_dialog.xml").unwrap().read_to_string(&mut content)?;
+        let table = parse_table_xml(&content, "text_ui_dialog.xml")?;
+        Ok(table.get(&EntryId(entry_id.to_string().into())).unwrap().0.to_string())
+    }
+
+    #[test]
+    fn fallback_chain_is_tried_in_order() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let out = tempfile::tempdir()?;
+        let pairs = parse_bilingual_set_toml(
+            r#"
+            [[pair]]
+            primary = "English"
+            secondary = "German"
+            fallback = ["German", "Chineses", "English"]
+
+            [[pair]]
+            primary = "Chineses"
+            secondary = "German"
+            fallback = []
+            "#,
+        )?;
+        assert_eq!(pairs[0].languages(), vec!["English", "German", "Chineses"]);
+        assert_eq!(pairs[1].languages(), vec!["Chineses", "German"]);
+
+        let mut generator = common::fixture_generator(game.path(), out.path())?;
+        generator.language_to_process = vec!["Chineses".to_string(), "English".to_string(), "German".to_string()];
+        generator.read_xml_from_paks()?;
+
+        // German lacks d_bye, the next language of the chain that has it is used
+        let output_dir = generator.process_single_bilingual(&pairs[0])?;
+        assert_eq!(dialog_text(&output_dir.join("English_xml.pak"), "d_bye")?, "Goodbye\\n再见");
+        // Without a fallback the primary text is left alone
+        let output_dir = generator.process_single_bilingual(&pairs[1])?;
+        assert_eq!(dialog_text(&output_dir.join("Chineses_xml.pak"), "d_bye")?, "再见");
+        assert_eq!(dialog_text(&output_dir.join("Chineses_xml.pak"), "d_hello")?, "你好，亨利。\\nHallo, Heinrich.");
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn coordinator_only_waits_for_the_chain() -> Result<(), BilingualGeneratorError> {
+        // No English pak at all
+        let game = tempfile::tempdir()?;
+        for language in ["Chineses", "German"] {
+            let files: Vec<(&str, String)> = common::language_rows(language)
+                .into_iter()
+                .map(|(name, rows)| (name, common::table_xml(&rows)))
+                .collect();
+            common::write_pak(game.path(), language, &files);
+        }
+        let out = tempfile::tempdir()?;
+        let generator = common::fixture_generator(game.path(), out.path())?;
+
+        let mut without_fallback = BilingualPair::new("Chineses", "German");
+        without_fallback.fallback = Some(Vec::new());
+        let messages = generator.generate_bilingual_pairs_async(vec![without_fallback]).await?;
+        assert_eq!(messages.len(), 1);
+
+        // The default chain needs English, which cannot be read
+        assert!(
+            generator
+                .generate_bilingual_pairs_async(vec![BilingualPair::new("Chineses", "German")])
+                .await
+                .is_err()
+        );
+        Ok(())
+    }
+}
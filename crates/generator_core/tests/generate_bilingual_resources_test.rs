@@ -11,9 +11,9 @@ mod tests {
         let bilingual_set = generator.acquire_bilingual_set()?;
         match generator.read_xml_from_paks() {
             Ok(_) => {
-                for (primary_language, secondary_language) in bilingual_set {
-                    println!("primary_language = {}, secondary_language = {}", primary_language, secondary_language);
-                    match generator.process_single_bilingual(&primary_language, &secondary_language) {
+                for pair in bilingual_set {
+                    println!("{}", pair.describe());
+                    match generator.process_single_bilingual(&pair) {
                         Ok(_) => continue,
                         Err(e) => return Err(e),
                     }
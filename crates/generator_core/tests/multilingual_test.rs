@@ -0,0 +1,48 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common;
+    use generator_core::{
+        bilingual_generator::EntryId, bilingual_generator_errors::BilingualGeneratorError, bilingual_set::parse_bilingual_set_toml,
+        combiner::pair_output_dir, util::parse_table_xml,
+    };
+    use std::io::Read;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn every_extra_language_is_written_in_order() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let out = tempfile::tempdir()?;
+        let pairs = parse_bilingual_set_toml(
+            r#"
+            [[pair]]
+            primary = "Chineses"
+            secondary = ["German", "English"]
+            include = ["text_ui_dialog.xml"]
+            "#,
+        )?;
+        assert_eq!(pairs[0].output_name(), "Chineses + German + English");
+
+        let mut sync_generator = common::fixture_generator(game.path(), &out.path().join("sync"))?;
+        sync_generator.language_to_process = pairs[0].languages().iter().map(|language| language.to_string()).collect();
+        sync_generator.read_xml_from_paks()?;
+        let sync_dir = sync_generator.process_single_bilingual(&pairs[0])?;
+
+        let async_generator = common::fixture_generator(game.path(), &out.path().join("async"))?;
+        async_generator.generate_bilingual_pairs_async(pairs.clone()).await?;
+        let async_dir = pair_output_dir(&out.path().join("async"), &pairs[0]);
+
+        for output_dir in [sync_dir, async_dir] {
+            let mut archive = zip::ZipArchive::new(std::fs::File::open(output_dir.join("Chineses_xml.pak"))?).unwrap();
+            let mut content = String::new();
+            archive.by_name("text_ui_dialog.xml").unwrap().read_to_string(&mut content)?;
+            let table = parse_table_xml(&content, "text_ui_dialog.xml")?;
+            let text = |id: &str| table.get(&EntryId(id.to_string().into())).unwrap().0.to_string();
+            assert_eq!(text("d_hello"), "你好，亨利。\\nHallo, Heinrich.\\nHello, Henry.");
+            // German lacks d_bye; the English fallback is already shown, so it is not repeated
+            assert_eq!(text("d_bye"), "再见\\nGoodbye");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+#[cfg(test)]
+mod tests {
+    use generator_core::{
+        bilingual_generator::{EntryId, LastTextValue},
+        bilingual_generator_errors::BilingualGeneratorError,
+        combine_rules::{RuleSet, TextOrder},
+        combiner::{CombineSettings, LanguageTable, PairReport, PairTables, build_table_xml},
+        normalization::Normalization,
+        overrides::Overrides,
+        util::parse_table_xml,
+    };
+
+    fn combine(rules: &RuleSet, primary: &str, secondaries: &[Option<&str>]) -> String {
+        rules
+            .combine(
+                "text_ui_items.xml",
+                &EntryId("id".into()),
+                &LastTextValue(primary.to_string().into()),
+                secondaries,
+                None,
+            )
+            .to_string()
+    }
+
+    #[test]
+    fn texts_are_composed_and_trimmed() {
+        let normalization = Normalization::default();
+        assert_eq!(normalization.text(" Cafe\u{301} \\n\\n"), "Café");
+        assert_eq!(normalization.text("第一行\\n第二行\n"), "第一行\\n第二行");
+        assert!(matches!(normalization.text("苹果"), std::borrow::Cow::Borrowed(_)));
+        let off = Normalization {
+            nfc: false,
+            trim: false,
+            separators: false,
+        };
+        assert_eq!(off.text(" e\u{301}"), " e\u{301}");
+    }
+
+    #[test]
+    fn separators_follow_the_neighbouring_scripts() {
+        let normalization = Normalization {
+            separators: true,
+            ..Normalization::default()
+        };
+        assert_eq!(normalization.separator("/", "确定", "好的"), "／");
+        assert_eq!(normalization.separator(" ／ ", "你好，亨利。", "<i>再见</i>"), "／");
+        assert_eq!(normalization.separator("/", "苹果", "Apple"), " / ");
+        assert_eq!(normalization.separator("／", "Game Options", "游戏选项"), " / ");
+        assert_eq!(normalization.separator("，", "Apple", "Pear"), ", ");
+        assert_eq!(normalization.separator(" | ", "Сила", "力量"), " | ");
+        // Line breaks and other separators stay
+        assert_eq!(normalization.separator("\\n", "苹果", "Apple"), "\\n");
+        assert_eq!(normalization.separator(" - ", "苹果", "Apple"), " - ");
+        assert_eq!(Normalization::default().separator("/", "苹果", "Apple"), "/");
+    }
+
+    #[test]
+    fn rule_sets_normalize_joined_texts() -> Result<(), BilingualGeneratorError> {
+        let rules = RuleSet::from_toml_str(
+            r#"
+            [normalize]
+            separators = true
+
+            [[rule]]
+            name = "slash"
+            action = "slash"
+            "#,
+        )?;
+        assert_eq!(combine(&rules, "苹果 ", &[Some("Apple\\n")]), "苹果 / Apple");
+        assert_eq!(combine(&rules, "苹果", &[Some("林檎"), Some("Apple")]), "苹果／林檎 / Apple");
+        // A text shown alone is tidied up the same way
+        assert_eq!(combine(&rules, "苹果 ", &[Some("  ")]), "苹果");
+
+        let mut secondary_first = rules.clone();
+        secondary_first.order = TextOrder::SecondaryFirst;
+        assert_eq!(combine(&secondary_first, "确定", &[Some("OK")]), "OK / 确定");
+
+        let overrides = Overrides::from_toml_str("[ids]\nid = \"slash\"")?;
+        let text = overrides.get("text_ui_items.xml", "id").unwrap().apply(
+            &LastTextValue("游戏选项".into()),
+            &["Game Options "],
+            &rules.separators,
+            &rules.templates,
+            rules.order,
+            &rules.normalization,
+        );
+        assert_eq!(text, "游戏选项 / Game Options");
+
+        // Separators are only adapted on request
+        let plain = RuleSet::from_toml_str("[[rule]]\nname = \"slash\"\naction = \"slash\"")?;
+        assert_eq!(combine(&plain, "苹果 ", &[Some("Apple")]), "苹果/Apple");
+        assert!(RuleSet::from_toml_str("[normalize]\nspacing = true").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn primary_only_texts_are_normalized() -> Result<(), BilingualGeneratorError> {
+        let rules = RuleSet::from_toml_str(
+            r#"
+            [[rule]]
+            name = "menus"
+            files = ["text_ui_menus.xml"]
+            action = "slash"
+
+            [[rule]]
+            name = "short"
+            max_primary_len = 2
+            action = "primary_only"
+            "#,
+        )?;
+        // Decided by `primary_only`, and by no rule at all
+        assert_eq!(combine(&rules, " 苹果\\n", &[Some("Apple")]), "苹果");
+        assert_eq!(combine(&rules, " 红彤彤的苹果 ", &[Some("A red apple")]), "红彤彤的苹果");
+
+        let overrides = Overrides::from_toml_str("[ids]\nid = \"primary_only\"")?;
+        let text = overrides.get("text_ui_items.xml", "id").unwrap().apply(
+            &LastTextValue("苹果 ".into()),
+            &["Apple"],
+            &rules.separators,
+            &rules.templates,
+            rules.order,
+            &rules.normalization,
+        );
+        assert_eq!(text, "苹果");
+
+        // A combination suppressed as redundant
+        let primary: LanguageTable = [(EntryId("ui_ok".into()), LastTextValue("OK ".into()))].into_iter().collect();
+        let secondary: LanguageTable = [(EntryId("ui_ok".into()), LastTextValue("OK".into()))].into_iter().collect();
+        let tables = PairTables {
+            primary: Some(&primary),
+            secondaries: vec![Some(&secondary)],
+            fallbacks: Vec::new(),
+        };
+        let settings = CombineSettings {
+            rule_set: &rules,
+            overrides: &Overrides::default(),
+            decision_log: false,
+        };
+        let mut report = PairReport::default();
+        let xml = build_table_xml("text_ui_menus.xml", &primary, tables, settings, &mut report, &mut Vec::new())?;
+        assert_eq!(report.suppressed, [0, 1, 0]);
+        assert_eq!(parse_table_xml(&xml, "text_ui_menus.xml")?.get(&EntryId("ui_ok".into())).unwrap().0, "OK");
+        Ok(())
+    }
+}
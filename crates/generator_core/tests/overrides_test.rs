@@ -0,0 +1,151 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common;
+    use generator_core::{
+        bilingual_generator::{EntryId, LastTextValue},
+        bilingual_generator_errors::BilingualGeneratorError,
+        bilingual_set::BilingualPair,
+        combine_rules::RuleSet,
+        overrides::{EntryOverride, OverrideAction, Overrides},
+        util::parse_table_xml,
+    };
+    use std::{io::Read, path::Path};
+
+    const OVERRIDES: &str = r#"
+        [ids]
+        ui_ok = "secondary_first"
+        q_main = { action = "primary_only" }
+        apple = "primary_only"
+        no_such_entry = "slash"
+
+        [files."text_ui_items.xml"]
+        apple = "newline"
+
+        [files."text_ui_soul.xml"]
+        stat_str = { text = "力量 (STR)" }
+
+        [files."text_ui_menus.xml"]
+        d_hello = "slash"
+        "#;
+
+    fn entry_text(output_dir: &Path, file_name: &str, entry_id: &str) -> Result<String, BilingualGeneratorError> {
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(output_dir.join("Chineses_xml.pak"))?).unwrap();
+        let mut content = String::new();
+        archive.by_name(file_name).unwrap().read_to_string(&mut content)?;
+        let table = parse_table_xml(&content, file_name)?;
+        Ok(table.get(&EntryId(entry_id.to_string().into())).unwrap().0.to_string())
+    }
+
+    #[test]
+    fn overrides_parse_and_file_scope_wins() -> Result<(), BilingualGeneratorError> {
+        let overrides = Overrides::from_toml_str(OVERRIDES)?;
+        assert_eq!(
+            overrides.get("text_ui_menus.xml", "ui_ok"),
+            Some(&EntryOverride::Action(OverrideAction::SecondaryFirst))
+        );
+        assert_eq!(
+            overrides.get("text_ui_items.xml", "apple"),
+            Some(&EntryOverride::Action(OverrideAction::Newline))
+        );
+        assert_eq!(
+            overrides.get("text_ui_quest.xml", "apple"),
+            Some(&EntryOverride::Action(OverrideAction::PrimaryOnly))
+        );
+        assert_eq!(
+            overrides.get("text_ui_soul.xml", "stat_str"),
+            Some(&EntryOverride::Text("力量 (STR)".to_string()))
+        );
+        assert_eq!(overrides.get("text_ui_items.xml", "stat_str"), None);
+
+        for invalid in [
+            "[ids]\nui_ok = \"bold\"",
+            "[ids]\nui_ok = { action = \"slash\", text = \"OK\" }",
+            "[ids]\nui_ok = {}",
+            "[idz]\nui_ok = \"slash\"",
+        ] {
+            assert!(Overrides::from_toml_str(invalid).is_err(), "{:?} should be rejected", invalid);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn secondary_first_override_matches_the_equivalent_rule() -> Result<(), BilingualGeneratorError> {
+        let entry_override = EntryOverride::Action(OverrideAction::SecondaryFirst);
+        let primary = LastTextValue("游戏选项".into());
+        for rules in [
+            "[separators]\nnewline = \"/\"\n[normalize]\nseparators = true",
+            "[templates]\nnewline = \"{secondary} ({primary})\"",
+        ] {
+            let rules = RuleSet::from_toml_str(&format!("order = \"secondary_first\"\n{}\n[[rule]]\nname = \"n\"\naction = \"newline\"", rules))?;
+            let by_rule = rules.combine("text_ui_menus.xml", &EntryId("ui_options".into()), &primary, &[Some("Game Options")], None);
+            let by_override = entry_override.apply(
+                &primary,
+                &["Game Options"],
+                &rules.separators,
+                &rules.templates,
+                rules.order,
+                &rules.normalization,
+            );
+            assert_eq!(by_override, by_rule);
+        }
+        assert_eq!(
+            entry_override.apply(
+                &primary,
+                &["Game Options"],
+                &RuleSet::default().separators,
+                &Default::default(),
+                Default::default(),
+                &Default::default()
+            ),
+            "Game Options\\n游戏选项"
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn overrides_replace_rule_output_and_are_reported() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let out = tempfile::tempdir()?;
+        std::fs::create_dir_all(out.path().join("assets"))?;
+        std::fs::write(out.path().join("assets").join("overrides.toml"), OVERRIDES)?;
+
+        let mut generator = common::fixture_generator(game.path(), out.path())?;
+        generator.overrides = Overrides::load(out.path())?;
+        generator.read_xml_from_paks()?;
+
+        let pair = BilingualPair::new("Chineses", "English");
+        let report = generator.process_single_bilingual_report(&pair)?;
+        let output_dir = &report.output_dir;
+        // The default rules leave a short menu entry monolingual
+        assert_eq!(entry_text(output_dir, "text_ui_menus.xml", "ui_ok")?, "OK\\n确定");
+        assert_eq!(entry_text(output_dir, "text_ui_quest.xml", "q_main")?, "主线任务");
+        assert_eq!(entry_text(output_dir, "text_ui_quest.xml", "q_side")?, "支线/Side");
+        assert_eq!(entry_text(output_dir, "text_ui_items.xml", "apple")?, "苹果\\nApple");
+        assert_eq!(entry_text(output_dir, "text_ui_soul.xml", "stat_str")?, "力量 (STR)");
+
+        assert_eq!(report.overrides_applied.len(), 4);
+        assert_eq!(
+            report.overrides_unused,
+            vec!["no_such_entry".to_string(), "text_ui_menus.xml:d_hello".to_string()]
+        );
+        let summary = report.summary(&pair);
+        assert!(summary.contains("4 override(s) applied: "), "{}", summary);
+        assert!(
+            summary.contains("Unused override(s), no such entry: no_such_entry, text_ui_menus.xml:d_hello"),
+            "{}",
+            summary
+        );
+
+        // The async orchestrator reports the same
+        let messages = generator.generate_bilingual_pairs_async(vec![pair.clone()]).await?;
+        assert_eq!(messages, vec![summary]);
+
+        // A language swap is left alone
+        let report = generator.process_single_bilingual_report(&BilingualPair::parse_line("Chineses -> English")?)?;
+        assert!(report.overrides_applied.is_empty());
+        Ok(())
+    }
+}
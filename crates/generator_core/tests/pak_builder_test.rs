@@ -0,0 +1,121 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common;
+    use generator_core::{bilingual_generator_errors::BilingualGeneratorError, bilingual_set::BilingualPair, pak_builder::PakBuilder};
+    use std::io::{Read, Write};
+    use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+    #[test]
+    fn pak_builder_writes_entries_in_order() -> Result<(), BilingualGeneratorError> {
+        let out = tempfile::tempdir()?;
+        let mut pak = PakBuilder::new();
+        pak.add_file("b.xml", b"<Table></Table>")?;
+        pak.add_file("a.xml", b"<Table>a</Table>")?;
+        let pak_path = pak.finish(&out.path().join("Localization"), "English_xml.pak")?;
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&pak_path)?).unwrap();
+        assert_eq!(archive.file_names().collect::<Vec<_>>().len(), 2);
+        assert_eq!(archive.by_index(0).unwrap().name(), "b.xml");
+        let mut content = String::new();
+        archive.by_name("a.xml").unwrap().read_to_string(&mut content)?;
+        assert_eq!(content, "<Table>a</Table>");
+        Ok(())
+    }
+
+    #[test]
+    fn untouched_entries_are_copied_byte_for_byte() -> Result<(), BilingualGeneratorError> {
+        let dir = tempfile::tempdir()?;
+        let original_pak = dir.path().join("Chineses_xml.pak");
+        let mut original = ZipWriter::new(std::fs::File::create(&original_pak)?);
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        original.start_file("text_ui_dialog.xml", SimpleFileOptions::default()).unwrap();
+        original.write_all(b"<Table>old</Table>")?;
+        original.start_file("font.cfg", stored).unwrap();
+        original.write_all(b"binary\0data")?;
+        original.start_file("text_ui_credits.xml", SimpleFileOptions::default()).unwrap();
+        original.write_all("<Table>制作人员</Table>".repeat(20).as_bytes())?;
+        original.finish().unwrap();
+
+        let mut pak = PakBuilder::new();
+        pak.add_file("text_ui_dialog.xml", b"<Table>new</Table>")?;
+        assert_eq!(pak.copy_remaining_from(&original_pak)?, 2);
+        let pak_path = pak.finish(&dir.path().join("out"), "Chineses_xml.pak")?;
+
+        let mut original = zip::ZipArchive::new(std::fs::File::open(&original_pak)?).unwrap();
+        let mut output = zip::ZipArchive::new(std::fs::File::open(&pak_path)?).unwrap();
+        assert_eq!(output.len(), 3);
+        let mut content = String::new();
+        output.by_name("text_ui_dialog.xml").unwrap().read_to_string(&mut content)?;
+        assert_eq!(content, "<Table>new</Table>");
+        for name in ["font.cfg", "text_ui_credits.xml"] {
+            let source = original.by_name(name).unwrap();
+            let (crc32, compressed_size, compression) = (source.crc32(), source.compressed_size(), source.compression());
+            drop(source);
+            let copied = output.by_name(name).unwrap();
+            assert_eq!(
+                (copied.crc32(), copied.compressed_size(), copied.compression()),
+                (crc32, compressed_size, compression)
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn files_that_are_not_tables_keep_their_bytes() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let fonts_config = "<FontsConfig>\n  <Font name=\"default\" file=\"fonts/kcd.ttf\"/>\n</FontsConfig>\n";
+        let mut files: Vec<(&str, String)> = common::language_rows("Chineses")
+            .into_iter()
+            .map(|(name, rows)| (name, common::table_xml(&rows)))
+            .collect();
+        files.push(("fonts_config.xml", fonts_config.to_string()));
+        common::write_pak(game.path(), "Chineses", &files);
+
+        let out = tempfile::tempdir()?;
+        let mut generator = common::fixture_generator(game.path(), out.path())?;
+        generator.read_xml_from_paks()?;
+        assert!(generator.files_to_process.contains(&"fonts_config.xml".to_string()));
+        let output_dir = generator.process_single_bilingual(&BilingualPair::new("Chineses", "English"))?;
+
+        let mut output = zip::ZipArchive::new(std::fs::File::open(output_dir.join("Chineses_xml.pak"))?).unwrap();
+        let mut content = Vec::new();
+        output.by_name("fonts_config.xml").unwrap().read_to_end(&mut content)?;
+        assert_eq!(content, fonts_config.as_bytes());
+        Ok(())
+    }
+
+    #[test]
+    fn generation_leaves_only_the_pak() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let out = tempfile::tempdir()?;
+        let mut generator = common::fixture_generator(game.path(), out.path())?;
+        generator.read_xml_from_paks()?;
+        let output_dir = generator.process_single_bilingual(&BilingualPair::new("Chineses", "English"))?;
+
+        let entries: Vec<String> = std::fs::read_dir(&output_dir)?
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(entries, vec!["Chineses_xml.pak".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn failed_generation_writes_nothing() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let out = tempfile::tempdir()?;
+        let mut generator = common::fixture_generator(game.path(), out.path())?;
+        generator.read_xml_from_paks()?;
+        // A file where the output directory should go makes the final write fail
+        std::fs::write(out.path().join("bilingual_xml"), b"not a directory")?;
+
+        assert!(generator.process_single_bilingual(&BilingualPair::new("Chineses", "English")).is_err());
+        let entries: Vec<_> = std::fs::read_dir(out.path())?.collect();
+        assert_eq!(entries.len(), 1);
+        Ok(())
+    }
+}
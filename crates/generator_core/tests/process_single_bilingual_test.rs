@@ -3,6 +3,7 @@ mod tests {
     use generator_core::{
         bilingual_generator::{BilingualGenerator /*, EntryId, Language, XmlFile */},
         bilingual_generator_errors::BilingualGeneratorError,
+        bilingual_set::BilingualPair,
     };
     use path_finder::PathFinder;
     #[test]
@@ -32,7 +33,7 @@ mod tests {
         // let content = xml_data.get(&entry_id).unwrap();
         // println!("content it get = {}", content.0);
         // Call the read_xml_from_paks function to parse XML files
-        match generator.process_single_bilingual("Chineset", "English") {
+        match generator.process_single_bilingual(&BilingualPair::new("Chineset", "English")) {
             Ok(_) => Ok(()),
             Err(e) => return Err(e),
         }
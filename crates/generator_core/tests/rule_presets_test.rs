@@ -0,0 +1,107 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common;
+    use generator_core::{
+        bilingual_generator::EntryId,
+        bilingual_generator_errors::BilingualGeneratorError,
+        bilingual_set::BilingualPair,
+        combine_rules::{PRESETS, RuleSet},
+        text_metrics::Script,
+        util::parse_table_xml,
+    };
+    use std::{io::Read, path::Path};
+
+    fn menu_text(output_dir: &Path, pak_name: &str, entry_id: &str) -> Result<String, BilingualGeneratorError> {
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(output_dir.join(pak_name))?).unwrap();
+        let mut content = String::new();
+        archive.by_name("text_ui_menus.xml").unwrap().read_to_string(&mut content)?;
+        let table = parse_table_xml(&content, "text_ui_menus.xml")?;
+        Ok(table.get(&EntryId(entry_id.to_string().into())).unwrap().0.to_string())
+    }
+
+    #[test]
+    fn script_is_known_or_detected() {
+        assert_eq!(Script::of_language("Chineset"), Some(Script::Cjk));
+        assert_eq!(Script::of_language("German"), Some(Script::Latin));
+        assert_eq!(Script::of_language("Ukrainian"), Some(Script::Cyrillic));
+        assert_eq!(Script::of_language("Klingon"), None);
+        assert_eq!(Script::detect(["Привет, Генри.", "OK"]), Some(Script::Cyrillic));
+        assert_eq!(Script::detect(["你好，亨利。", "OK"]), Some(Script::Cjk));
+        assert_eq!(Script::detect(["Ça va, Henri ?"]), Some(Script::Latin));
+        assert_eq!(Script::detect(["123 !?"]), None);
+        for (name, _) in PRESETS {
+            assert_eq!(RuleSet::preset(name).unwrap().preset, Some(name));
+        }
+        assert_eq!(RuleSet::default().preset, Some("cjk"));
+    }
+
+    #[test]
+    fn preset_follows_the_primary_script() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let out = tempfile::tempdir()?;
+        let mut generator = common::fixture_generator(game.path(), out.path())?;
+        generator.read_xml_from_paks()?;
+
+        // A short Chinese label stays monolingual, a short English one has room for Chinese
+        let output_dir = generator.process_single_bilingual(&BilingualPair::new("Chineses", "English"))?;
+        assert_eq!(menu_text(&output_dir, "Chineses_xml.pak", "ui_ok")?, "确定");
+        let output_dir = generator.process_single_bilingual(&BilingualPair::new("English", "Chineses"))?;
+        assert_eq!(menu_text(&output_dir, "English_xml.pak", "ui_ok")?, "OK / 确定");
+        assert_eq!(menu_text(&output_dir, "English_xml.pak", "ui_helpoverlay_a")?, "Help");
+        // Naming the default rules still picks the preset by script
+        let mut named_default = BilingualPair::new("English", "Chineses");
+        named_default.rule_set = Some("default".to_string());
+        let output_dir = generator.process_single_bilingual(&named_default)?;
+        assert_eq!(menu_text(&output_dir, "English_xml.pak", "ui_ok")?, "OK / 确定");
+
+        // Learner mode is only used on request
+        let mut learner = BilingualPair::new("English", "Chineses");
+        learner.rule_set = Some("learner".to_string());
+        learner.output_name = Some("learner".to_string());
+        let output_dir = generator.process_single_bilingual(&learner)?;
+        assert_eq!(menu_text(&output_dir, "English_xml.pak", "ui_ok")?, "OK / 确定");
+
+        // The user's own default rules apply to every primary language
+        let rules = out.path().join("assets").join("rules");
+        std::fs::create_dir_all(&rules)?;
+        std::fs::write(rules.join("default.toml"), "[[rule]]\nname = \"all\"\naction = \"primary_only\"\n")?;
+        generator.rule_set = RuleSet::load(out.path())?;
+        assert_eq!(generator.rule_set.preset, None);
+        let output_dir = generator.process_single_bilingual(&BilingualPair::new("English", "Chineses"))?;
+        assert_eq!(menu_text(&output_dir, "English_xml.pak", "ui_options")?, "Game Options");
+        Ok(())
+    }
+
+    fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                copy_dir(&entry.path(), &to.join(entry.file_name()))?;
+            } else {
+                std::fs::copy(entry.path(), to.join(entry.file_name()))?;
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn shipped_assets_keep_the_script_presets() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        // The assets folder as it is shipped next to the executable
+        let out = tempfile::tempdir()?;
+        copy_dir(&Path::new(env!("CARGO_MANIFEST_DIR")).join("../../assets"), &out.path().join("assets"))?;
+        let mut generator = common::fixture_generator(game.path(), out.path())?;
+        generator.rule_set = RuleSet::load(out.path())?;
+        assert_eq!(generator.rule_set.preset, Some("cjk"));
+        generator.read_xml_from_paks()?;
+
+        let output_dir = generator.process_single_bilingual(&BilingualPair::new("English", "Chineses"))?;
+        assert_eq!(menu_text(&output_dir, "English_xml.pak", "ui_ok")?, "OK / 确定");
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod tests {
+    use generator_core::{
+        bilingual_generator::{EntryId, LastTextValue},
+        bilingual_generator_errors::BilingualGeneratorError,
+        combine_rules::RuleSet,
+        sentence_alignment::{align_sentences, first_sentence, split_sentences},
+    };
+
+    #[test]
+    fn sentences_split_on_cjk_and_latin_punctuation() {
+        assert_eq!(
+            split_sentences("你好，亨利。今天天气不错！走吧？"),
+            vec!["你好，亨利。", "今天天气不错！", "走吧？"]
+        );
+        assert_eq!(
+            split_sentences("Hello, Henry. The weather is fine!  Shall we go?"),
+            vec!["Hello, Henry.", "The weather is fine!", "Shall we go?"]
+        );
+        // Closing quotes stay with their sentence, numbers are not cut
+        assert_eq!(split_sentences("他说：“走！”好吧。"), vec!["他说：“走！”", "好吧。"]);
+        assert_eq!(
+            split_sentences("It costs 3.5 groschen... \"Fine.\" Done"),
+            vec!["It costs 3.5 groschen...", "\"Fine.\"", "Done"]
+        );
+        // Line breaks end a sentence and are dropped
+        assert_eq!(split_sentences("第一行\\n第二行"), vec!["第一行", "第二行"]);
+        assert!(split_sentences("  ").is_empty());
+    }
+
+    #[test]
+    fn first_sentence_ends_in_an_ellipsis() {
+        assert_eq!(first_sentence("A red apple. Heals a little.").as_deref(), Some("A red apple…"));
+        assert_eq!(first_sentence("好吃！还能回血。").as_deref(), Some("好吃…"));
+        assert_eq!(first_sentence("Run! They are coming.").as_deref(), Some("Run…"));
+        assert_eq!(first_sentence("Really?! Yes.").as_deref(), Some("Really…"));
+        assert_eq!(first_sentence("一个红彤彤的苹果。"), None);
+    }
+
+    #[test]
+    fn alignment_needs_matching_sentences() {
+        assert_eq!(
+            align_sentences("你好，亨利。今天天气不错！", "Hello, Henry. The weather is nice today!"),
+            Some(vec![("你好，亨利。", "Hello, Henry."), ("今天天气不错！", "The weather is nice today!")])
+        );
+        // A single sentence is simply stacked
+        assert_eq!(align_sentences("你好，亨利。", "Hello, Henry."), None);
+        // Different sentence counts
+        assert_eq!(align_sentences("你好。今天天气不错。走吧。", "Hello. Nice weather today, let's go."), None);
+        // Same count, but the lengths do not line up
+        assert_eq!(
+            align_sentences(
+                "好。今天天气不错，我们一起去城里的集市看看吧。",
+                "Well, that is a long way to walk for nothing. Yes."
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn aligned_rules_interleave_sentence_pairs() -> Result<(), BilingualGeneratorError> {
+        let rules = RuleSet::from_toml_str(
+            r#"
+            [styles]
+            "text_ui_dialog.xml" = "<i>{text}</i>"
+
+            [[rule]]
+            name = "dialog"
+            files = ["text_ui_dialog.xml"]
+            action = "newline"
+            align_sentences = true
+            "#,
+        )?;
+        let combine = |primary: &str, secondary: &str| {
+            rules
+                .combine(
+                    "text_ui_dialog.xml",
+                    &EntryId("d_long".into()),
+                    &LastTextValue(primary.to_string().into()),
+                    &[Some(secondary)],
+                    None,
+                )
+                .to_string()
+        };
+        assert_eq!(
+            combine("你好，亨利。今天天气不错！", "Hello, Henry. The weather is nice today!"),
+            "你好，亨利。\\n<i>Hello, Henry.</i>\\n今天天气不错！\\n<i>The weather is nice today!</i>"
+        );
+        // Falls back to stacking the whole texts
+        assert_eq!(
+            combine("你好。今天天气不错。走吧。", "Hello. Nice weather today, let's go."),
+            "你好。今天天气不错。走吧。\\n<i>Hello. Nice weather today, let's go.</i>"
+        );
+
+        assert!(RuleSet::from_toml_str("[[rule]]\nname = \"x\"\naction = \"primary_only\"\nalign_sentences = true").is_err());
+        Ok(())
+    }
+}
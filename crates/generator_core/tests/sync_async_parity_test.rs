@@ -0,0 +1,44 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common;
+    use generator_core::{bilingual_generator_errors::BilingualGeneratorError, bilingual_set::BilingualPair};
+
+    #[tokio::test(flavor = "multi_thread")]
+    // Both entry points must produce byte-identical paks from the same game files.
+    async fn sync_and_async_paks_are_identical() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let sync_out = tempfile::tempdir()?;
+        let async_out = tempfile::tempdir()?;
+
+        let mut sync_generator = common::fixture_generator(game.path(), sync_out.path())?;
+        sync_generator.language_to_process = common::LANGUAGES.map(String::from).to_vec();
+        sync_generator.read_xml_from_paks()?;
+        let mut async_generator = common::fixture_generator(game.path(), async_out.path())?;
+        async_generator.language_to_process = common::LANGUAGES.map(String::from).to_vec();
+
+        let pairs = vec![
+            BilingualPair::new("Chineses", "English"),
+            BilingualPair::new("Chineses", "German"),
+            BilingualPair::new("German", "English").with_target_slot("Chineses"),
+        ];
+        for pair in &pairs {
+            sync_generator.process_single_bilingual(pair)?;
+        }
+        async_generator.generate_bilingual_pairs_async(pairs.clone()).await?;
+
+        for pair in &pairs {
+            let relative = std::path::Path::new("bilingual_xml")
+                .join(pair.output_name())
+                .join("Localization")
+                .join(pair.pak_name());
+            let sync_pak = std::fs::read(sync_out.path().join(&relative))?;
+            let async_pak = std::fs::read(async_out.path().join(&relative))?;
+            assert!(!sync_pak.is_empty());
+            assert_eq!(sync_pak, async_pak, "{} differs between sync and async paths", pair);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common;
+    use faststr::FastStr;
+    use generator_core::{
+        bilingual_generator::{EntryId, LastTextValue},
+        bilingual_generator_errors::BilingualGeneratorError,
+        bilingual_set::BilingualPair,
+        table_xml::{TableWriter, validate_table_xml},
+        util::parse_table_xml,
+    };
+    use std::io::Read;
+
+    #[test]
+    fn cells_are_escaped_and_round_trip() -> Result<(), BilingualGeneratorError> {
+        let mut table = TableWriter::new(Vec::new())?;
+        let entry_id = EntryId("ui_amp".into());
+        let primary = LastTextValue("Tom & Jerry <3".into());
+        let combined: FastStr = "Tom & Jerry <3/汤姆 & 杰瑞 > 猫".into();
+        table.write_row(&entry_id, &primary, &combined)?;
+        let (xml, written) = table.finish()?;
+        let xml = String::from_utf8(xml).unwrap();
+
+        assert!(xml.contains("Tom &amp; Jerry &lt;3"));
+        assert!(!xml.contains("& Jerry"));
+        validate_table_xml("text_ui_menus.xml", &xml, &written)?;
+        let parsed = parse_table_xml(&xml, "test")?;
+        assert_eq!(parsed.get(&entry_id).unwrap().0, combined);
+        Ok(())
+    }
+
+    #[test]
+    fn validation_reports_mismatching_entries() {
+        let xml = "<Table>\n<Row><Cell>a</Cell><Cell>x</Cell><Cell>wrong</Cell></Row>\n</Table>";
+        let result = validate_table_xml("text_ui_menus.xml", xml, &[(EntryId("a".into()), "right".into())]);
+        assert!(matches!(result, Err(BilingualGeneratorError::XmlValidationFailed { entry_id, .. }) if entry_id == "a"));
+        let result = validate_table_xml(
+            "text_ui_menus.xml",
+            "<Table><Row><Cell>a</Cell></Table>",
+            &[(EntryId("a".into()), "right".into())],
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn generated_pak_contains_valid_xml() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        common::fixture_game(game.path());
+        let out = tempfile::tempdir()?;
+        let mut generator = common::fixture_generator(game.path(), out.path())?;
+        generator.read_xml_from_paks()?;
+        let output_dir = generator.process_single_bilingual(&BilingualPair::new("Chineses", "English"))?;
+
+        let pak = std::fs::File::open(output_dir.join("Chineses_xml.pak"))?;
+        let mut archive = zip::ZipArchive::new(pak).unwrap();
+        let mut dialog = String::new();
+        archive.by_name("text_ui_dialog.xml").unwrap().read_to_string(&mut dialog)?;
+        assert!(dialog.contains("汤姆 &amp; 杰瑞\\nTom &amp; Jerry"));
+        let parsed = parse_table_xml(&dialog, "text_ui_dialog.xml")?;
+        assert_eq!(parsed.get(&EntryId("d_amp".into())).unwrap().0, "汤姆 & 杰瑞\\nTom & Jerry");
+        Ok(())
+    }
+}
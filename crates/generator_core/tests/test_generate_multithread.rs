@@ -14,9 +14,9 @@ mod tests {
 
         generator.read_xml_from_paks()?;
 
-        bilingual_set.par_iter().try_for_each(|(primary_language, secondary_language)| {
-            println!("primary_language = {}, secondary_language = {}", primary_language, secondary_language);
-            generator.process_single_bilingual(primary_language, secondary_language).map(|_| ())
+        bilingual_set.par_iter().try_for_each(|pair| {
+            println!("{}", pair.describe());
+            generator.process_single_bilingual(pair).map(|_| ())
         })?;
 
         Ok(())
@@ -0,0 +1,172 @@
+#[cfg(test)]
+mod tests {
+    use generator_core::{
+        bilingual_generator::{EntryId, LastTextValue},
+        bilingual_generator_errors::BilingualGeneratorError,
+        combine_rules::RuleSet,
+        text_metrics::{FontMetrics, LengthMetric, Redundancy, char_count, display_width, rendered_width, truncate_to_width},
+    };
+    use std::path::{Path, PathBuf};
+
+    fn game_font() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../../assets/times new roman.ttf")
+    }
+
+    #[test]
+    fn width_counts_cjk_twice_and_skips_escapes() {
+        assert_eq!(char_count("游戏选项"), 4);
+        assert_eq!(display_width("游戏选项"), 8);
+        assert_eq!(display_width("Options"), 7);
+        // Fullwidth punctuation is wide too
+        assert_eq!(display_width("你好，亨利。"), 12);
+        // The literal `\n` separator is not drawn
+        assert_eq!(char_count("确定\\nOK"), 4);
+        assert_eq!(display_width("确定\\nOK"), 6);
+        // Combining marks take no column of their own
+        assert_eq!(display_width("e\u{301}te\u{301}"), 3);
+        assert_eq!(LengthMetric::default().measure("确定"), 2);
+        assert_eq!(LengthMetric::Width.measure("确定"), 4);
+        // Markup tags are not drawn either, a lone `<` is
+        assert_eq!(char_count("确定\\n<font color=\"#a0a0a0\">OK</font>"), 4);
+        assert_eq!(display_width("<font size='18'>确定</font>"), 4);
+        assert_eq!(char_count("1 < 2 <3> <"), 11);
+    }
+
+    #[test]
+    fn truncation_keeps_whole_words_and_characters() {
+        assert_eq!(truncate_to_width("A red apple.", 12), None);
+        assert_eq!(truncate_to_width("A red apple.", 10).as_deref(), Some("A red…"));
+        // Wide characters take two columns, the ellipsis one
+        assert_eq!(truncate_to_width("一个红彤彤的苹果。", 8).as_deref(), Some("一个红…"));
+        // A single long word is cut inside
+        assert_eq!(truncate_to_width("Longsword", 5).as_deref(), Some("Long…"));
+        // Escapes and markup take no columns and are never split
+        assert_eq!(truncate_to_width("<b>Sharp</b> blade", 9).as_deref(), Some("<b>Sharp</b>…"));
+        // Tags open at the cut are closed after the ellipsis
+        assert_eq!(
+            truncate_to_width("<font color=\"#a0a0a0\">long <i>grey</i> text</font>", 10).as_deref(),
+            Some("<font color=\"#a0a0a0\">long <i>grey…</i></font>")
+        );
+        assert_eq!(truncate_to_width("<b><i>Very long words</i></b>", 6).as_deref(), Some("<b><i>Very…</i></b>"));
+        assert_eq!(truncate_to_width("Line<br/>break here", 9).as_deref(), Some("Line<br/>brea…"));
+    }
+
+    #[test]
+    fn redundant_texts_are_recognised() {
+        assert_eq!(Redundancy::of("Henry", "Henry"), Some(Redundancy::Identical));
+        // Case, whitespace, diacritics and markup do not count
+        assert_eq!(Redundancy::of("Été", "ete "), Some(Redundancy::NearIdentical));
+        assert_eq!(Redundancy::of("<b>Rattay</b>", "RATTAY"), Some(Redundancy::NearIdentical));
+        // Numbers, punctuation and placeholders alone add nothing
+        assert_eq!(Redundancy::of("100 格罗申", "%d"), Some(Redundancy::NoWords));
+        assert_eq!(Redundancy::of("第 {0} 天", "{0} / $DAYS$ ..."), Some(Redundancy::NoWords));
+        assert_eq!(Redundancy::of("确定", "OK"), None);
+        assert_eq!(Redundancy::of("Stärke", "Starke Ritter"), None);
+    }
+
+    #[test]
+    fn rule_length_conditions_use_their_metric() -> Result<(), BilingualGeneratorError> {
+        let rules = RuleSet::from_toml_str(
+            r#"
+            [[rule]]
+            name = "short_by_width"
+            files = ["text_ui_menus.xml"]
+            max_primary_len = 6
+            length_metric = "width"
+            action = "primary_only"
+
+            [[rule]]
+            name = "short_by_chars"
+            files = ["text_ui_items.xml"]
+            max_primary_len = 6
+            action = "primary_only"
+
+            [[rule]]
+            name = "rest"
+            action = "slash"
+            "#,
+        )?;
+        let combine = |file: &str, primary: &str| {
+            rules
+                .combine(file, &EntryId("id".into()), &LastTextValue(primary.to_string().into()), &[Some("Other")], None)
+                .to_string()
+        };
+        // Six Chinese characters are twelve columns wide, as wide as twelve Latin letters
+        assert_eq!(combine("text_ui_menus.xml", "游戏选项设置"), "游戏选项设置/Other");
+        assert_eq!(combine("text_ui_menus.xml", "游戏选"), "游戏选");
+        assert_eq!(combine("text_ui_menus.xml", "Option"), "Option");
+        assert_eq!(combine("text_ui_items.xml", "游戏选项设置"), "游戏选项设置");
+        assert!(RuleSet::from_toml_str("[[rule]]\nname = \"x\"\naction = \"slash\"\nlength_metric = \"pixels\"").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn font_widths_follow_glyph_advances() -> Result<(), BilingualGeneratorError> {
+        let font = FontMetrics::load(&game_font())?;
+        assert!(font.line_width("iiii", 24.0) < font.line_width("WWWW", 24.0));
+        assert!((font.line_width("Options", 48.0) - 2.0 * font.line_width("Options", 24.0)).abs() < 0.01);
+        // The widest line counts
+        let two_lines = rendered_width("Hi\\nHello there", 24.0, Some(&font));
+        assert_eq!(two_lines, font.line_width("Hello there", 24.0));
+        // Without a font a column is half an em
+        assert_eq!(rendered_width("确定/OK", 20.0, None), 70.0);
+        assert!(FontMetrics::load(Path::new("missing.ttf")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn rules_fall_through_until_the_text_fits() -> Result<(), BilingualGeneratorError> {
+        let rules_toml = r#"
+            [font]
+            path = "times new roman.ttf"
+            size = 20
+
+            [[rule]]
+            name = "slash_if_it_fits"
+            action = "slash"
+            max_width_px = 150
+
+            [[rule]]
+            name = "newline_if_it_fits"
+            action = "newline"
+            max_width_px = 150
+
+            [[rule]]
+            name = "primary_only"
+            action = "primary_only"
+            "#;
+        let mut rules = RuleSet::from_toml_str(rules_toml)?;
+        rules.load_font(game_font().parent().unwrap())?;
+        let combine = |rules: &RuleSet, primary: &str, secondary: &str| {
+            let (rule, text) = rules
+                .resolve(
+                    "text_ui_menus.xml",
+                    &EntryId("id".into()),
+                    &LastTextValue(primary.to_string().into()),
+                    &[Some(secondary)],
+                    None,
+                )
+                .unwrap();
+            (rule.name.clone(), text.to_string())
+        };
+        assert_eq!(
+            combine(&rules, "Save", "Speichern"),
+            ("slash_if_it_fits".to_string(), "Save/Speichern".to_string())
+        );
+        assert_eq!(
+            combine(&rules, "Save the game", "Spiel speichern"),
+            ("newline_if_it_fits".to_string(), "Save the game\\nSpiel speichern".to_string())
+        );
+        assert_eq!(
+            combine(&rules, "Save the game now", "Das Spiel jetzt sofort speichern"),
+            ("primary_only".to_string(), "Save the game now".to_string())
+        );
+
+        // Without the font the width is estimated from the characters
+        let rules = RuleSet::from_toml_str(rules_toml)?;
+        assert_eq!(combine(&rules, "存档", "Speichern").0, "slash_if_it_fits");
+        assert_eq!(combine(&rules, "保存当前游戏", "Spiel speichern").0, "newline_if_it_fits");
+        assert!(RuleSet::from_toml_str(rules_toml)?.load_font(Path::new("/nonexistent")).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common;
+    use generator_core::{
+        bilingual_generator::{EntryId, LastTextValue},
+        bilingual_generator_errors::BilingualGeneratorError,
+        bilingual_set::BilingualPair,
+        combine_rules::RuleSet,
+        text_metrics::{char_count, display_width},
+        tokens::{Token, placeholders, strip_markup, tokenize},
+    };
+
+    #[test]
+    fn tokens_cover_the_whole_text() {
+        let text = "Got <font color=\"#ffd700\">%d</font> groschen\\n{0} 100% done $PLAYER$";
+        let tokens: Vec<Token> = tokenize(text).into_iter().map(|(_, token)| token).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Text("Got "),
+                Token::Tag("<font color=\"#ffd700\">"),
+                Token::Placeholder("%d"),
+                Token::Tag("</font>"),
+                Token::Text(" groschen"),
+                Token::Escape("\\n"),
+                Token::Placeholder("{0}"),
+                Token::Text(" 100% done "),
+                Token::Placeholder("$PLAYER$"),
+            ]
+        );
+        let rebuilt: String = tokenize(text).into_iter().map(|(_, token)| token.as_str()).collect();
+        assert_eq!(rebuilt, text);
+        assert_eq!(tokenize("1 < 2 { x }"), vec![(0, Token::Text("1 < 2 { x }"))]);
+        assert_eq!(tokenize("%1$s和%2$s")[1], (4, Token::Text("和")));
+    }
+
+    #[test]
+    fn placeholders_and_markup_take_no_length() {
+        assert_eq!(char_count("获得%d格罗申"), 5);
+        assert_eq!(display_width("{0}/{1}"), 1);
+        assert_eq!(char_count("<b>%1$s</b> wins"), 5);
+        assert_eq!(placeholders("%d of %d, {0} and %d").into_iter().collect::<Vec<_>>(), vec!["%d", "{0}"]);
+        assert_eq!(strip_markup("<i>Hello</i>, <b>%s</b>\\n"), "Hello, %s\\n");
+        assert!(matches!(strip_markup("1 < 2"), std::borrow::Cow::Borrowed("1 < 2")));
+    }
+
+    #[test]
+    fn rules_strip_secondary_markup() -> Result<(), BilingualGeneratorError> {
+        let rules = RuleSet::from_toml_str(
+            r#"
+            [styles]
+            "*" = "<i>{text}</i>"
+
+            [[rule]]
+            name = "plain"
+            action = "slash"
+            strip_markup = true
+            "#,
+        )?;
+        let combined = rules.combine(
+            "text_ui_items.xml",
+            &EntryId("gold".into()),
+            &LastTextValue("<b>金币</b> %d".into()),
+            &[Some("<font color=\"#ffd700\">Gold</font> %d")],
+            None,
+        );
+        assert_eq!(combined, "<b>金币</b> %d/<i>Gold %d</i>");
+        Ok(())
+    }
+
+    #[test]
+    fn placeholder_mismatches_are_reported() -> Result<(), BilingualGeneratorError> {
+        let game = tempfile::tempdir()?;
+        let rows = [
+            ("Chineses", vec![("gold", "%d 格罗申"), ("days", "第 {0} 天"), ("name", "亨利")]),
+            ("English", vec![("gold", "%d groschen"), ("days", "Day"), ("name", "Henry")]),
+        ];
+        for (language, rows) in rows {
+            common::write_pak(game.path(), language, &[("text_ui_items.xml", common::table_xml(&rows))]);
+        }
+        let out = tempfile::tempdir()?;
+        let mut generator = common::fixture_generator(game.path(), out.path())?;
+        generator.language_to_process = vec!["Chineses".to_string(), "English".to_string()];
+        generator.read_xml_from_paks()?;
+
+        let pair = BilingualPair::new("Chineses", "English");
+        let report = generator.process_single_bilingual_report(&pair)?;
+        assert_eq!(report.placeholder_mismatches, vec![("text_ui_items.xml".to_string(), "days".to_string())]);
+        assert!(
+            report
+                .summary(&pair)
+                .contains("Placeholders differ from the primary text in 1 entry(ies): days")
+        );
+        Ok(())
+    }
+}
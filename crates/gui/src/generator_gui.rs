@@ -155,12 +155,12 @@ impl GuiBilingualPakGenerator {
 
         let messages: Vec<String> = bilingual_set
             .par_iter()
-            .map(|(primary_language, secondary_language)| {
+            .map(|pair| {
                 // Perform processing first
-                let result = generator.process_single_bilingual(primary_language, secondary_language);
+                let result = generator.process_single_bilingual_report(pair);
 
                 // Then create message (after potential error)
-                result.map(|_| format!("primary_language = {}, secondary_language = {}", primary_language, secondary_language))
+                result.map(|report| report.summary(pair))
             })
             .collect::<Result<Vec<String>, _>>()?;
 
@@ -0,0 +1,197 @@
+use generator_core::{
+    bilingual_generator::BilingualGenerator,
+    bilingual_generator_errors::BilingualGeneratorError,
+    bilingual_set::{BilingualPair, migrate_bilingual_set},
+    calibration::{CALIBRATED_FILES, CalibrationTargets, calibrate},
+    combiner::pair_rule_set,
+    decision_log::explain_entry,
+    text_metrics::{LengthMetric, Script},
+};
+use std::path::PathBuf;
+
+static USAGE: &str = "\
+Usage: kcd_bilingual_generator_rust [COMMAND]
+
+Without a command the GUI is started.
+
+Commands:
+  check-set      Check the bilingual set against the installed game languages
+  migrate-set    Convert assets/bilingual_set.txt into assets/bilingual_set.toml
+  generate [--explain]
+                 Generate every pair of the bilingual set without the GUI
+      --explain             Also write decisions.csv, the rule behind every entry, for each pair
+  explain <ENTRY_ID> [--file <NAME>]
+                 Show which rule decided an entry in the last generation run with --explain
+  calibrate <Primary> <Secondary> [OPTIONS]
+                 Propose rule thresholds from the length distribution of the game texts
+      --slash <PERCENT>     Share of entries written as primary/secondary (default 60)
+      --newline <PERCENT>   Share of entries written on two lines (default 30)
+      --metric <chars|width>  How text length is measured (default chars)
+      --file <NAME>         XML file to calibrate, repeatable (default menus, items, soul)
+      --write <NAME>        Save the proposed rules as assets/rules/<NAME>.toml
+  help           Show this message";
+
+/// Runs a command line subcommand and returns the process exit code.
+pub fn run(args: &[String]) -> i32 {
+    let result = match args.first().map(String::as_str) {
+        Some("check-set") => check_set(),
+        Some("migrate-set") => migrate_set(),
+        Some("generate") => match args[1..] {
+            [] => generate(false),
+            [ref flag] if flag == "--explain" => generate(true),
+            _ => {
+                eprintln!("generate only accepts --explain\n\n{}", USAGE);
+                return 2;
+            }
+        },
+        Some("explain") => match &args[1..] {
+            [entry_id] => explain(entry_id, None),
+            [entry_id, flag, file] if flag == "--file" => explain(entry_id, Some(file)),
+            _ => {
+                eprintln!("explain needs an entry id and optionally --file <NAME>\n\n{}", USAGE);
+                return 2;
+            }
+        },
+        Some("calibrate") => match CalibrateArgs::parse(&args[1..]) {
+            Ok(calibrate_args) => run_calibration(calibrate_args),
+            Err(message) => {
+                eprintln!("{}\n\n{}", message, USAGE);
+                return 2;
+            }
+        },
+        Some("help" | "--help" | "-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(other) => {
+            eprintln!("Unknown command '{}'.\n\n{}", other, USAGE);
+            return 2;
+        }
+        None => Ok(()),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    }
+}
+
+fn working_dir() -> PathBuf {
+    std::env::current_dir().unwrap_or_else(|_| PathBuf::new())
+}
+
+fn check_set() -> Result<(), BilingualGeneratorError> {
+    let mut generator = BilingualGenerator::init()?;
+    let pairs = generator.acquire_bilingual_set()?;
+    println!("Bilingual set is valid, {} pair(s):", pairs.len());
+    for pair in pairs {
+        println!("  {}", pair);
+    }
+    Ok(())
+}
+
+fn migrate_set() -> Result<(), BilingualGeneratorError> {
+    let toml_path = migrate_bilingual_set(&working_dir())?;
+    println!("Wrote {}", toml_path.display());
+    Ok(())
+}
+
+fn generate(decision_log: bool) -> Result<(), BilingualGeneratorError> {
+    let mut generator = BilingualGenerator::init()?;
+    generator.decision_log = decision_log;
+    let pairs = generator.acquire_bilingual_set()?;
+    generator.read_xml_from_paks()?;
+    for pair in &pairs {
+        println!("{}", generator.process_single_bilingual_report(pair)?.summary(pair));
+    }
+    Ok(())
+}
+
+fn explain(entry_id: &str, file: Option<&str>) -> Result<(), BilingualGeneratorError> {
+    let decisions = explain_entry(&working_dir(), entry_id, file)?;
+    if decisions.is_empty() {
+        println!("No decision logged for '{}', run `generate --explain` first.", entry_id);
+    }
+    for (pair, decision) in decisions {
+        let secondary_len = decision.secondary_len.map_or("-".to_string(), |len| len.to_string());
+        println!(
+            "{} / {}: {} (primary {}, secondary {}, in {})\n  {}",
+            pair,
+            decision.file,
+            decision.decided_by,
+            decision.primary_len,
+            secondary_len,
+            decision.length_metric.name(),
+            decision.text
+        );
+    }
+    Ok(())
+}
+
+struct CalibrateArgs {
+    primary: String,
+    secondary: String,
+    targets: CalibrationTargets,
+    metric: LengthMetric,
+    files: Vec<String>,
+    write: Option<String>,
+}
+
+impl CalibrateArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut languages = Vec::new();
+        let mut targets = CalibrationTargets::default();
+        let mut metric = LengthMetric::Chars;
+        let mut files = Vec::new();
+        let mut write = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().cloned().ok_or_else(|| format!("{} needs a value", arg));
+            let percent = |value: String| value.parse::<f64>().map_err(|_| format!("{} needs a number, found '{}'", arg, value));
+            match arg.as_str() {
+                "--slash" => targets.slash = percent(value()?)?,
+                "--newline" => targets.newline = percent(value()?)?,
+                "--metric" => {
+                    let name = value()?;
+                    metric = LengthMetric::from_name(&name).ok_or_else(|| format!("unknown metric '{}', use chars or width", name))?;
+                }
+                "--file" => files.push(value()?),
+                "--write" => write = Some(value()?),
+                option if option.starts_with("--") => return Err(format!("unknown option '{}'", option)),
+                language => languages.push(language.to_string()),
+            }
+        }
+        let [primary, secondary] = <[String; 2]>::try_from(languages).map_err(|_| "calibrate needs a primary and a secondary language".to_string())?;
+        if files.is_empty() {
+            files = CALIBRATED_FILES.iter().map(|f| f.to_string()).collect();
+        }
+        Ok(Self {
+            primary,
+            secondary,
+            targets,
+            metric,
+            files,
+            write,
+        })
+    }
+}
+
+fn run_calibration(args: CalibrateArgs) -> Result<(), BilingualGeneratorError> {
+    let generator = BilingualGenerator::init()?;
+    let calibration = calibrate(&generator.game_path, &args.primary, &args.secondary, &args.files, args.metric, args.targets)?;
+    println!("{}", calibration.report());
+    // The thresholds go into the rules the pair is combined with today
+    let pair = BilingualPair::new(args.primary.clone(), args.secondary.clone());
+    let script = Script::of_language(&args.primary).unwrap_or(Script::Cjk);
+    let base = pair_rule_set(&working_dir(), &generator.rule_set, &pair, script)?;
+    match args.write {
+        Some(name) => {
+            let path = calibration.write_rule_set(&working_dir(), &name, &base)?;
+            println!("Wrote {}, select it with rule_set = \"{}\"", path.display(), name);
+        }
+        None => println!("Proposed rules:\n\n{}", calibration.to_rule_set_toml(&base)?),
+    }
+    Ok(())
+}
@@ -1,6 +1,12 @@
+mod cli;
+
 use gui::generator_gui::GuiBilingualPakGenerator;
 
 fn main() -> Result<(), eframe::Error> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args));
+    }
     let options = eframe::NativeOptions::default();
     eframe::run_native(
         "Bilingual Pak Generator",